@@ -0,0 +1,58 @@
+//! 无窗口地跑一局游戏，直到玩家死亡
+//!
+//! `cargo run --example headless -- --seed 42`
+//!
+//! 加上 `--replay <file>` 时以最快速度回放录像，`--record <file>` 时把这一局录下来，
+//! `--level <file>` 时玩这个关卡而不是无尽模式
+
+use bevy::prelude::*;
+use dodge_the_creeps_bevy::{
+    headless_app, FixedTick, GameConfig, GameMode, GameState, Level, Playback, Recorder, RngSeed,
+    Score,
+};
+
+fn main() {
+    let mut app = headless_app();
+    app.insert_resource(RngSeed::from_args_or_env())
+        .insert_resource(FixedTick::from_args_or_env());
+    match GameConfig::load("assets/game.config.ron") {
+        Ok(config) => {
+            app.insert_resource(config);
+        }
+        Err(e) => println!("using default config: {e}"),
+    }
+    if let Some(path) = std::env::args().skip_while(|a| a != "--level").nth(1) {
+        let level =
+            Level::load(&path).unwrap_or_else(|e| panic!("failed to load level {path}: {e}"));
+        app.insert_resource(GameMode::Level(level));
+    }
+    if let Some(recorder) = Recorder::from_args() {
+        app.insert_resource(recorder);
+    }
+    if let Some(playback) = Playback::from_args() {
+        app.insert_resource(playback);
+        app.world.resource_mut::<FixedTick>().set_time_scale(1000.0);
+    }
+    let mut frames = 0_u64;
+    while app.world.resource::<State<GameState>>().current() == &GameState::Playing {
+        if app
+            .world
+            .get_resource::<Playback>()
+            .is_some_and(|p| p.remaining() == 0)
+        {
+            println!("replay ran out of frames");
+            break;
+        }
+        app.update();
+        frames += 1;
+    }
+    println!("game over after {frames} frames");
+    let score = app.world.resource::<Score>();
+    println!(
+        "score {} ({} near misses, best streak {}, {} pickups)",
+        score.points(),
+        score.near_misses,
+        score.best_streak,
+        score.pickups
+    );
+}
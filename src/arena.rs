@@ -0,0 +1,235 @@
+//! 游戏区域：玩家被限制在 [`Arena`] 的边界里面，敌人从边界外的一圈出现，
+//! 离开更外面的一圈后被移除
+//!
+//! 边界可以是任意的凸多边形，也可以带曲线，敌人也可以改从单独的一条路径上出现
+//!
+//! 运行时修改 [`Arena`] 资源（比如逐渐缩小），玩家、敌人和音效都会跟着变化
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::collision::CollisionRole;
+use crate::common::path::{Path, PathPoint};
+use crate::config::GameConfig;
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Arena>()
+            .add_system(reset_arena)
+            .add_system(sync_arena_wall.after(reset_arena));
+    }
+}
+
+/// 配置文件里的矩形游戏区域，以屏幕中心为原点
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArenaConfig {
+    /// 玩家可以活动的半宽和半高
+    pub half_size: Vec2,
+    /// 敌人出现的位置在边界外多远
+    pub spawn_margin: f32,
+    /// 敌人离开边界多远之后被移除
+    pub despawn_margin: f32,
+    /// 敌人出现的路径，不设置时用边界外 `spawn_margin` 处的一圈
+    pub spawn_path: Option<Path>,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            half_size: Vec2::new(200.0, 360.0),
+            spawn_margin: 0.0,
+            despawn_margin: 50.0,
+            spawn_path: None,
+        }
+    }
+}
+
+impl ArenaConfig {
+    /// 游戏区域不为空，出现的路径不退化成一个点
+    ///
+    /// # Errors
+    ///
+    /// 返回第一个无效的值
+    pub fn validate(&self) -> Result<(), String> {
+        let half_size = self.half_size;
+        if !(half_size.is_finite() && half_size.x > 0.0 && half_size.y > 0.0) {
+            return Err(format!(
+                "arena.half_size: must be positive, got {half_size}"
+            ));
+        }
+        if !(self.spawn_margin.is_finite() && self.despawn_margin.is_finite()) {
+            return Err("arena: margins must be finite".to_owned());
+        }
+        if let Some(path) = &self.spawn_path {
+            path.validate()
+                .map_err(|e| format!("arena.spawn_path: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// 沿着边界的墙，调试时可以看到边界，见 [`CollisionRole::Wall`]
+#[derive(Component)]
+struct ArenaWall;
+
+pub struct Arena {
+    /// 闭合的凸多边形
+    bounds: Path,
+    spawn: Path,
+    despawn: Path,
+    /// 自定义的出现路径，不随边界变化
+    spawn_path: Option<Path>,
+    spawn_margin: f32,
+    despawn_margin: f32,
+}
+
+impl Arena {
+    /// `bounds` 必须是闭合的凸多边形，可以带曲线
+    #[must_use]
+    pub fn new(bounds: Path, spawn_margin: f32, despawn_margin: f32) -> Self {
+        Self {
+            spawn: bounds.expand(spawn_margin),
+            despawn: bounds.expand(despawn_margin),
+            bounds,
+            spawn_path: None,
+            spawn_margin,
+            despawn_margin,
+        }
+    }
+
+    /// 敌人从 `path` 上出现，而不是边界外的一圈
+    #[must_use]
+    pub fn with_spawn_path(mut self, path: Path) -> Self {
+        self.spawn = path.clone();
+        self.spawn_path = Some(path);
+        self
+    }
+
+    /// 换一个边界，保留原来的两个距离和自定义的出现路径
+    pub fn set_bounds(&mut self, bounds: Path) {
+        self.spawn = self
+            .spawn_path
+            .clone()
+            .unwrap_or_else(|| bounds.expand(self.spawn_margin));
+        self.despawn = bounds.expand(self.despawn_margin);
+        self.bounds = bounds;
+    }
+
+    #[must_use]
+    pub const fn bounds(&self) -> &Path {
+        &self.bounds
+    }
+
+    /// 边界里离 `point` 最近的点
+    #[must_use]
+    pub fn clamp(&self, point: Vec2) -> Vec2 {
+        self.bounds.clamp(point)
+    }
+
+    /// 敌人出现的路径，法线指向区域外面
+    #[must_use]
+    pub const fn spawn_path(&self) -> &Path {
+        &self.spawn
+    }
+
+    /// 按长度均匀地随机取一个敌人出现的位置
+    pub fn random_spawn_point<R: Rng + ?Sized>(&self, rng: &mut R) -> PathPoint {
+        self.spawn.random_point(rng)
+    }
+
+    /// 边界里面随机的一点，离边界至少 `margin`
+    pub fn random_point_inside<R: Rng + ?Sized>(&self, margin: f32, rng: &mut R) -> Vec2 {
+        let inner = self.bounds.expand(-margin);
+        let vertices = inner.vertices();
+        let min = vertices
+            .iter()
+            .copied()
+            .reduce(Vec2::min)
+            .unwrap_or_default();
+        let max = vertices
+            .iter()
+            .copied()
+            .reduce(Vec2::max)
+            .unwrap_or_default();
+        let random = |rng: &mut R| min + (max - min) * Vec2::new(rng.gen(), rng.gen());
+        // 在包围盒里随机，不在里面就重来，几次都不行就取最近的点
+        let mut point = random(rng);
+        for _ in 0..8 {
+            if inner.contains(point) {
+                return point;
+            }
+            point = random(rng);
+        }
+        inner.clamp(point)
+    }
+
+    /// 敌人到了这里就应该被移除
+    #[must_use]
+    pub fn is_outside(&self, point: Vec2) -> bool {
+        !self.despawn.contains(point)
+    }
+
+    /// 边界最右边的横坐标
+    #[must_use]
+    pub fn half_width(&self) -> f32 {
+        self.bounds
+            .vertices()
+            .iter()
+            .map(|p| p.x.abs())
+            .fold(0.0, f32::max)
+    }
+}
+
+impl From<ArenaConfig> for Arena {
+    fn from(config: ArenaConfig) -> Self {
+        let arena = Self::new(
+            Path::rect(config.half_size),
+            config.spawn_margin,
+            config.despawn_margin,
+        );
+        match config.spawn_path {
+            Some(path) => arena.with_spawn_path(path),
+            None => arena,
+        }
+    }
+}
+
+impl FromWorld for Arena {
+    fn from_world(world: &mut World) -> Self {
+        world.resource::<GameConfig>().arena.clone().into()
+    }
+}
+
+/// 配置变化时回到配置里的区域
+fn reset_arena(config: Res<GameConfig>, mut arena: ResMut<Arena>) {
+    if config.is_changed() {
+        *arena = config.arena.clone().into();
+    }
+}
+
+/// 边界变化时重新生成墙
+fn sync_arena_wall(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    walls: Query<Entity, With<ArenaWall>>,
+) {
+    if !arena.is_changed() {
+        return;
+    }
+    for entity in &walls {
+        commands.entity(entity).despawn();
+    }
+    let mut vertices = arena.bounds.vertices().to_vec();
+    vertices.push(vertices[0]);
+    commands
+        .spawn_bundle(TransformBundle::default())
+        .insert(ArenaWall)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::polyline(vertices, None))
+        .insert_bundle(CollisionRole::Wall.bundle());
+}
@@ -1,27 +1,28 @@
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
-use crate::common::GameState;
-use crate::loading::AudioAssets;
-
-pub struct GameOverEvent;
+use crate::music::{Music, MusicPlugin};
+use crate::settings::AudioSettings;
+use crate::sfx::{Sfx, SfxPlugin};
 
 pub struct InternalAudioPlugin;
 
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(AudioPlugin)
-            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(game_over_sound))
-            .add_event::<GameOverEvent>();
+            .add_plugin(MusicPlugin)
+            .add_plugin(SfxPlugin)
+            .add_system(apply_volume);
     }
 }
 
-fn game_over_sound(
-    audio: Res<Audio>,
-    audio_assets: Res<AudioAssets>,
-    mut events: EventReader<GameOverEvent>,
+fn apply_volume(
+    settings: Res<AudioSettings>,
+    music: Res<AudioChannel<Music>>,
+    sfx: Res<AudioChannel<Sfx>>,
 ) {
-    if events.iter().last().is_some() {
-        audio.play(audio_assets.gameover.clone());
+    if settings.is_changed() {
+        music.set_volume(settings.music_volume());
+        sfx.set_volume(settings.sfx_volume());
     }
 }
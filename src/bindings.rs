@@ -0,0 +1,281 @@
+//! 按键绑定：游戏里的每个操作对应最多两个按键和一个手柄按键，可以在设置里修改
+
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Pause,
+    Confirm,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+    ];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Up",
+            Action::MoveDown => "Down",
+            Action::MoveLeft => "Left",
+            Action::MoveRight => "Right",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+        }
+    }
+
+    /// 在游戏中和在菜单里是否会用到，两个操作在同一个场合都会用到时不能共用按键
+    const fn contexts(self) -> (bool, bool) {
+        match self {
+            Action::MoveUp | Action::MoveDown | Action::MoveLeft | Action::MoveRight => {
+                (true, true)
+            }
+            Action::Pause => (true, false),
+            Action::Confirm | Action::Back => (false, true),
+        }
+    }
+
+    const fn overlaps(self, other: Self) -> bool {
+        let (a_game, a_menu) = self.contexts();
+        let (b_game, b_menu) = other.contexts();
+        (a_game && b_game) || (a_menu && b_menu)
+    }
+}
+
+/// 一个操作绑定的按键
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Binding {
+    pub keys: [Option<KeyCode>; 2],
+    pub button: Option<GamepadButtonType>,
+}
+
+impl Binding {
+    const fn new(keys: [Option<KeyCode>; 2], button: GamepadButtonType) -> Self {
+        Self {
+            keys,
+            button: Some(button),
+        }
+    }
+
+    fn shares_input(&self, other: &Self) -> bool {
+        self.keys
+            .iter()
+            .flatten()
+            .any(|key| other.keys.contains(&Some(*key)))
+            || (self.button.is_some() && self.button == other.button)
+    }
+}
+
+/// 绑定可以修改的位置：两个按键和一个手柄按键
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BindingSlot {
+    Key(usize),
+    Button,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub move_up: Binding,
+    pub move_down: Binding,
+    pub move_left: Binding,
+    pub move_right: Binding,
+    pub pause: Binding,
+    pub confirm: Binding,
+    pub back: Binding,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use GamepadButtonType as Pad;
+        Self {
+            move_up: Binding::new([Some(KeyCode::Up), Some(KeyCode::W)], Pad::DPadUp),
+            move_down: Binding::new([Some(KeyCode::Down), Some(KeyCode::S)], Pad::DPadDown),
+            move_left: Binding::new([Some(KeyCode::Left), Some(KeyCode::A)], Pad::DPadLeft),
+            move_right: Binding::new([Some(KeyCode::Right), Some(KeyCode::D)], Pad::DPadRight),
+            pause: Binding::new([Some(KeyCode::Escape), Some(KeyCode::P)], Pad::Start),
+            confirm: Binding::new([Some(KeyCode::Return), None], Pad::South),
+            back: Binding::new([Some(KeyCode::Escape), None], Pad::East),
+        }
+    }
+}
+
+impl Bindings {
+    #[must_use]
+    pub const fn get(&self, action: Action) -> &Binding {
+        match action {
+            Action::MoveUp => &self.move_up,
+            Action::MoveDown => &self.move_down,
+            Action::MoveLeft => &self.move_left,
+            Action::MoveRight => &self.move_right,
+            Action::Pause => &self.pause,
+            Action::Confirm => &self.confirm,
+            Action::Back => &self.back,
+        }
+    }
+
+    pub const fn get_mut(&mut self, action: Action) -> &mut Binding {
+        match action {
+            Action::MoveUp => &mut self.move_up,
+            Action::MoveDown => &mut self.move_down,
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::Pause => &mut self.pause,
+            Action::Confirm => &mut self.confirm,
+            Action::Back => &mut self.back,
+        }
+    }
+
+    pub const fn set_key(&mut self, action: Action, slot: BindingSlot, key: Option<KeyCode>) {
+        if let BindingSlot::Key(i) = slot {
+            self.get_mut(action).keys[i] = key;
+        }
+    }
+
+    pub const fn set_button(&mut self, action: Action, button: Option<GamepadButtonType>) {
+        self.get_mut(action).button = button;
+    }
+
+    /// 和 `action` 在同一场合使用、又绑定了相同按键的操作
+    pub fn conflicts(&self, action: Action) -> impl Iterator<Item = Action> + '_ {
+        let binding = self.get(action);
+        Action::ALL.into_iter().filter(move |&other| {
+            other != action && action.overlaps(other) && binding.shares_input(self.get(other))
+        })
+    }
+}
+
+/// 按照 [`Bindings`] 读取键盘和手柄
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    bindings: Res<'w, Bindings>,
+    keys: ResMut<'w, Input<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    buttons: ResMut<'w, Input<GamepadButton>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl ActionInput<'_, '_> {
+    fn any(
+        &self,
+        action: Action,
+        key: impl Fn(KeyCode) -> bool,
+        button: impl Fn(GamepadButton) -> bool,
+    ) -> bool {
+        let binding = self.bindings.get(action);
+        binding.keys.iter().flatten().any(|&k| key(k))
+            || binding.button.is_some_and(|button_type| {
+                self.gamepads
+                    .iter()
+                    .any(|&gamepad| button(GamepadButton::new(gamepad, button_type)))
+            })
+    }
+
+    #[must_use]
+    pub fn pressed(&self, action: Action) -> bool {
+        self.any(
+            action,
+            |k| self.keys.pressed(k),
+            |b| self.buttons.pressed(b),
+        )
+    }
+
+    #[must_use]
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.any(
+            action,
+            |k| self.keys.just_pressed(k),
+            |b| self.buttons.just_pressed(b),
+        )
+    }
+
+    /// 是否刚按下，同时清掉这次按键
+    ///
+    /// 状态切换后同一帧里另一个状态的系统也会运行，不清掉的话会被处理两次
+    pub fn take(&mut self, action: Action) -> bool {
+        let binding = self.bindings.get(action);
+        let mut pressed = false;
+        for &key in binding.keys.iter().flatten() {
+            pressed |= self.keys.clear_just_pressed(key);
+        }
+        if let Some(button_type) = binding.button {
+            for &gamepad in self.gamepads.iter() {
+                pressed |= self
+                    .buttons
+                    .clear_just_pressed(GamepadButton::new(gamepad, button_type));
+            }
+        }
+        pressed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_have_no_conflicts() {
+        let bindings = Bindings::default();
+        for action in Action::ALL {
+            assert_eq!(bindings.conflicts(action).count(), 0, "{action:?}");
+        }
+    }
+
+    #[test]
+    fn same_key_in_the_same_context_conflicts() {
+        let mut bindings = Bindings::default();
+        bindings.set_key(Action::MoveUp, BindingSlot::Key(1), Some(KeyCode::S));
+        assert_eq!(
+            bindings.conflicts(Action::MoveUp).collect::<Vec<_>>(),
+            [Action::MoveDown]
+        );
+        assert_eq!(
+            bindings.conflicts(Action::MoveDown).collect::<Vec<_>>(),
+            [Action::MoveUp]
+        );
+    }
+
+    #[test]
+    fn same_button_conflicts() {
+        let mut bindings = Bindings::default();
+        bindings.set_button(Action::Back, Some(GamepadButtonType::South));
+        assert_eq!(
+            bindings.conflicts(Action::Back).collect::<Vec<_>>(),
+            [Action::Confirm]
+        );
+    }
+
+    #[test]
+    fn actions_in_different_contexts_can_share_a_key() {
+        // 默认的暂停和返回都是 Esc，一个只在游戏中用，一个只在菜单里用
+        let bindings = Bindings::default();
+        assert!(bindings.pause.shares_input(&bindings.back));
+        assert_eq!(bindings.conflicts(Action::Pause).count(), 0);
+    }
+
+    #[test]
+    fn unbound_slots_do_not_conflict() {
+        let mut bindings = Bindings::default();
+        bindings.set_button(Action::Confirm, None);
+        bindings.set_button(Action::Back, None);
+        assert_eq!(bindings.conflicts(Action::Confirm).count(), 0);
+    }
+}
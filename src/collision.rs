@@ -0,0 +1,115 @@
+//! 碰撞：每个碰撞体带一个 [`CollisionRole`]，由它决定碰撞分组，
+//! 碰撞事件按双方的角色转换成 [`PlayerHit`] 和 [`PlayerTouchedPickup`]
+//!
+//! 玩家和敌人都是运动学刚体，位置完全由游戏逻辑决定，物理引擎只负责检测重叠，不会推动它们
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::common::tick::{TickAppExt, TickStage};
+
+const PLAYER_GROUP: u32 = 0b1;
+const ENEMY_GROUP: u32 = 0b10;
+const PICKUP_GROUP: u32 = 0b100;
+const WALL_GROUP: u32 = 0b1000;
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerHit>()
+            .add_event::<PlayerTouchedPickup>()
+            .add_tick_system(TickStage::PostUpdate, resolve_collisions);
+    }
+}
+
+/// 碰撞体在游戏里的角色
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CollisionRole {
+    Player,
+    Enemy,
+    Pickup,
+    /// 游戏区域的边界，玩家由 [`Arena::clamp`](crate::arena::Arena::clamp) 限制在里面，
+    /// 敌人从外面穿过，所以不和任何东西碰撞
+    Wall,
+}
+
+impl CollisionRole {
+    const fn membership(self) -> u32 {
+        match self {
+            CollisionRole::Player => PLAYER_GROUP,
+            CollisionRole::Enemy => ENEMY_GROUP,
+            CollisionRole::Pickup => PICKUP_GROUP,
+            CollisionRole::Wall => WALL_GROUP,
+        }
+    }
+
+    /// 和哪些角色产生碰撞事件，敌人之间、敌人和道具之间互不理会
+    const fn filter(self) -> u32 {
+        match self {
+            CollisionRole::Player => ENEMY_GROUP | PICKUP_GROUP,
+            CollisionRole::Enemy | CollisionRole::Pickup => PLAYER_GROUP,
+            CollisionRole::Wall => 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn groups(self) -> CollisionGroups {
+        CollisionGroups::new(self.membership(), self.filter())
+    }
+
+    /// 不和 `other` 碰撞的分组，比如无敌的玩家不和敌人碰撞
+    #[must_use]
+    pub const fn groups_ignoring(self, other: Self) -> CollisionGroups {
+        CollisionGroups::new(self.membership(), self.filter() & !other.membership())
+    }
+
+    /// 以这个角色的身份查询物理引擎，只找 `other` 角色的碰撞体
+    #[must_use]
+    pub fn query(self, other: Self) -> QueryFilter<'static> {
+        QueryFilter::new()
+            .groups(CollisionGroups::new(self.membership(), other.membership()).into())
+    }
+
+    /// 角色和对应的分组，生成碰撞体时一起插入
+    #[must_use]
+    pub const fn bundle(self) -> (Self, CollisionGroups) {
+        (self, self.groups())
+    }
+}
+
+/// 玩家碰到了敌人
+pub struct PlayerHit {
+    pub enemy: Entity,
+}
+
+/// 玩家碰到了道具
+pub struct PlayerTouchedPickup {
+    pub pickup: Entity,
+}
+
+pub fn resolve_collisions(
+    mut events: EventReader<CollisionEvent>,
+    roles: Query<&CollisionRole>,
+    mut hits: EventWriter<PlayerHit>,
+    mut pickups: EventWriter<PlayerTouchedPickup>,
+) {
+    for event in events.iter() {
+        let CollisionEvent::Started(a, b, _) = *event else {
+            continue;
+        };
+        let (Ok(&role_a), Ok(&role_b)) = (roles.get(a), roles.get(b)) else {
+            continue;
+        };
+        let (other, other_role) = match (role_a, role_b) {
+            (CollisionRole::Player, role) => (b, role),
+            (role, CollisionRole::Player) => (a, role),
+            _ => continue,
+        };
+        match other_role {
+            CollisionRole::Enemy => hits.send(PlayerHit { enemy: other }),
+            CollisionRole::Pickup => pickups.send(PlayerTouchedPickup { pickup: other }),
+            CollisionRole::Player | CollisionRole::Wall => {}
+        }
+    }
+}
@@ -3,6 +3,8 @@
 
 pub mod animation;
 pub mod path;
+pub mod rng;
+pub mod tick;
 
 use bevy::prelude::*;
 
@@ -17,6 +19,14 @@ pub enum GameState {
     Playing,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    // After the player got hit the final score is shown here
+    GameOver,
+    // Pushed on top of Playing, the game is frozen until it gets popped again
+    Paused,
+    // Rebinding keys and gamepad buttons, entered from the menu
+    Controls,
+    // Volume and display settings, entered from the menu
+    Settings,
 }
 
 pub fn clear_entities<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
@@ -24,3 +34,21 @@ pub fn clear_entities<T: Component>(mut commands: Commands, query: Query<Entity,
         commands.entity(e).despawn_recursive();
     }
 }
+
+/// 读取命令行参数 `name` 后面紧跟的值，如 `--seed 42`
+pub fn arg_value(name: &str) -> Option<String> {
+    std::env::args().skip_while(|a| a != name).nth(1)
+}
+
+/// 检查配置里的 `min..=max` 范围，两端都是有限的数并且 `min <= max`，`name` 用在错误信息里
+///
+/// # Errors
+///
+/// 范围无效时返回错误信息
+pub fn check_range(name: &str, min: f32, max: f32) -> Result<(), String> {
+    if min.is_finite() && max.is_finite() && min <= max {
+        Ok(())
+    } else {
+        Err(format!("{name}: invalid range {min}..={max}"))
+    }
+}
@@ -1,38 +1,488 @@
+//! 由直线、三次贝塞尔曲线和圆弧连成的路径，可以闭合，类似 Godot 的 `Path2D`
+//!
+//! 曲线在创建时细分成折线，按长度取点（类似 `PathFollow2D` 的 `offset`），
+//! 所以随机取点时长的边被取到的机会更多
+
+use std::f32::consts::PI;
+
 use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// 路径上的一段，从上一段的终点开始
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Segment {
+    /// 直线，终点
+    Line(Vec2),
+    /// 三次贝塞尔曲线，两个控制点和终点
+    Cubic(Vec2, Vec2, Vec2),
+    /// 绕 `center` 转 `angle` 弧度的圆弧，正数为逆时针
+    Arc { center: Vec2, angle: f32 },
+}
 
-/// 多个点组成的包围路径
-pub struct Path<const N: usize>(pub [Vec2; N]);
+/// 贝塞尔曲线细分的段数
+const CUBIC_STEPS: usize = 16;
+/// 圆弧每一段最多转过的角度
+const ARC_STEP: f32 = PI / 16.0;
 
-/// 有向线段
-pub struct DirectedLineSegment {
-    /// 起点
-    begin: Vec2,
-    /// 方向，单位向量
-    rotation: Vec2,
-    /// 长度
-    len: f32,
+/// 配置文件里的写法
+#[derive(Clone, Serialize, Deserialize)]
+struct PathShape {
+    start: Vec2,
+    segments: Vec<Segment>,
+    /// 闭合的路径最后连回起点
+    #[serde(default)]
+    closed: bool,
 }
 
-impl<const N: usize> Path<N> {
-    pub fn random_segment(&self) -> DirectedLineSegment {
-        let i = rand::random::<usize>() % N;
-        let begin = self.0[i];
-        let end = self.0[(i + 1) % N];
-        let diff = end - begin;
-        DirectedLineSegment {
-            begin,
-            rotation: diff.normalize(),
-            len: diff.length(),
+/// 配置文件里少于两个点或者长度为零的路径读取时报错
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "PathShape", into = "PathShape")]
+pub struct Path {
+    shape: PathShape,
+    /// 细分之后的折线，闭合路径的最后一个点就是起点
+    points: Vec<Vec2>,
+    /// 从起点到每个点的长度
+    lengths: Vec<f32>,
+    /// 从起点到每一段终点的长度，闭合路径最后连回起点的一段也算
+    segment_ends: Vec<f32>,
+    /// 首尾相连之后是否是逆时针的，决定哪边是外面
+    counter_clockwise: bool,
+}
+
+/// 路径上的一个点
+#[derive(Clone, Copy, Debug)]
+pub struct PathPoint {
+    pub position: Vec2,
+    /// 前进方向，单位向量
+    pub tangent: Vec2,
+    /// 指向外面的法线，单位向量，开放的路径按首尾相连来判断里外
+    pub normal: Vec2,
+}
+
+impl TryFrom<PathShape> for Path {
+    type Error = String;
+
+    fn try_from(shape: PathShape) -> Result<Self, String> {
+        let path = Self::build(shape);
+        path.validate()?;
+        Ok(path)
+    }
+}
+
+impl From<Path> for PathShape {
+    fn from(path: Path) -> Self {
+        path.shape
+    }
+}
+
+impl Path {
+    fn build(shape: PathShape) -> Self {
+        let mut points = vec![shape.start];
+        // 每一段终点在 `points` 里的下标
+        let mut ends = Vec::with_capacity(shape.segments.len() + 1);
+        for &segment in &shape.segments {
+            let from = points[points.len() - 1];
+            flatten(from, segment, &mut points);
+            ends.push(points.len() - 1);
+        }
+        if shape.closed && points[points.len() - 1] != shape.start {
+            points.push(shape.start);
+            ends.push(points.len() - 1);
+        }
+        let mut lengths = Vec::with_capacity(points.len());
+        let mut length = 0.0;
+        lengths.push(length);
+        for pair in points.windows(2) {
+            length += pair[0].distance(pair[1]);
+            lengths.push(length);
+        }
+        let segment_ends = ends.into_iter().map(|i| lengths[i]).collect();
+        let area: f32 = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| a.perp_dot(*b))
+            .sum();
+        Self {
+            shape,
+            points,
+            lengths,
+            segment_ends,
+            counter_clockwise: area > 0.0,
+        }
+    }
+}
+
+/// 把 `segment` 细分之后的点（不含起点 `from`）加到 `points` 后面
+fn flatten(from: Vec2, segment: Segment, points: &mut Vec<Vec2>) {
+    match segment {
+        Segment::Line(end) => points.push(end),
+        Segment::Cubic(c1, c2, end) => points.extend((1..=CUBIC_STEPS).map(|i| {
+            #[allow(clippy::cast_precision_loss)]
+            let t = i as f32 / CUBIC_STEPS as f32;
+            let s = 1.0 - t;
+            from * (s * s * s) + c1 * (3.0 * s * s * t) + c2 * (3.0 * s * t * t) + end * (t * t * t)
+        })),
+        Segment::Arc { center, angle } => {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let steps = (angle.abs() / ARC_STEP).ceil().max(1.0) as usize;
+            let radius = from - center;
+            points.extend((1..=steps).map(|i| {
+                #[allow(clippy::cast_precision_loss)]
+                let t = i as f32 / steps as f32;
+                center + Vec2::from_angle(angle * t).rotate(radius)
+            }));
         }
     }
 }
 
-impl DirectedLineSegment {
-    pub fn random_point(&self) -> Vec2 {
-        self.begin + (rand::random::<f32>() * self.len) * self.rotation
+impl Path {
+    /// 从 `start` 开始的开放路径，用 [`Path::line_to`] 等方法往后接
+    #[must_use]
+    pub fn new(start: Vec2) -> Self {
+        Self::build(PathShape {
+            start,
+            segments: Vec::new(),
+            closed: false,
+        })
+    }
+
+    /// 依次连接 `points` 的闭合折线，`points` 为空时只有原点一个点
+    #[must_use]
+    pub fn polygon(points: &[Vec2]) -> Self {
+        let Some((&first, rest)) = points.split_first() else {
+            return Self::new(Vec2::ZERO).close();
+        };
+        rest.iter()
+            .fold(Self::new(first), |path, &point| path.line_to(point))
+            .close()
+    }
+
+    /// 以原点为中心的矩形，`half_size` 是半宽和半高，从左上角开始顺时针
+    #[must_use]
+    pub fn rect(half_size: Vec2) -> Self {
+        let Vec2 { x, y } = half_size;
+        Self::polygon(&[
+            Vec2::new(-x, y),
+            Vec2::new(x, y),
+            Vec2::new(x, -y),
+            Vec2::new(-x, -y),
+        ])
+    }
+
+    fn push(self, segment: Segment) -> Self {
+        let mut shape = self.shape;
+        shape.segments.push(segment);
+        Self::build(shape)
+    }
+
+    #[must_use]
+    pub fn line_to(self, end: Vec2) -> Self {
+        self.push(Segment::Line(end))
+    }
+
+    #[must_use]
+    pub fn cubic_to(self, c1: Vec2, c2: Vec2, end: Vec2) -> Self {
+        self.push(Segment::Cubic(c1, c2, end))
+    }
+
+    /// 绕 `center` 转 `angle` 弧度，正数为逆时针
+    #[must_use]
+    pub fn arc(self, center: Vec2, angle: f32) -> Self {
+        self.push(Segment::Arc { center, angle })
+    }
+
+    /// 连回起点
+    #[must_use]
+    pub fn close(self) -> Self {
+        let mut shape = self.shape;
+        shape.closed = true;
+        Self::build(shape)
+    }
+
+    /// 至少有两个点，长度大于零
+    ///
+    /// # Errors
+    ///
+    /// 路径退化成了一个点
+    pub fn validate(&self) -> Result<(), String> {
+        let length = self.length();
+        if self.points.len() < 2 {
+            Err("a path needs at least one segment".to_owned())
+        } else if !(length.is_finite() && length > 0.0) {
+            Err(format!("invalid path length {length}"))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[must_use]
+    pub const fn is_closed(&self) -> bool {
+        self.shape.closed
+    }
+
+    #[must_use]
+    pub fn length(&self) -> f32 {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    /// 细分之后的顶点，闭合路径不重复起点
+    #[must_use]
+    pub fn vertices(&self) -> &[Vec2] {
+        let end = self.points.len() - usize::from(self.is_closed() && self.points.len() > 1);
+        &self.points[..end]
+    }
+
+    /// 细分之后的每条边，起点和终点
+    fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        self.points.windows(2).map(|pair| (pair[0], pair[1]))
+    }
+
+    fn outward(&self, direction: Vec2) -> Vec2 {
+        if self.counter_clockwise {
+            -direction.perp()
+        } else {
+            direction.perp()
+        }
+    }
+
+    /// 离起点 `distance` 远的点，闭合的路径绕圈，开放的路径停在两端
+    ///
+    /// 只有一个点的路径总是返回这个点，方向为零
+    #[must_use]
+    pub fn sample(&self, distance: f32) -> PathPoint {
+        if self.points.len() < 2 {
+            return PathPoint {
+                position: self.points[0],
+                tangent: Vec2::ZERO,
+                normal: Vec2::ZERO,
+            };
+        }
+        let length = self.length();
+        let distance = if self.is_closed() && length > 0.0 {
+            distance.rem_euclid(length)
+        } else {
+            distance.clamp(0.0, length)
+        };
+        let i = self
+            .lengths
+            .partition_point(|&l| l <= distance)
+            .clamp(1, self.points.len() - 1);
+        let (begin, end) = (self.points[i - 1], self.points[i]);
+        let tangent = (end - begin).normalize_or_zero();
+        let position = begin + tangent * (distance - self.lengths[i - 1]);
+        PathPoint {
+            position,
+            tangent,
+            normal: self.outward(tangent),
+        }
+    }
+
+    /// 按长度均匀地随机取一个点
+    pub fn random_point<R: Rng + ?Sized>(&self, rng: &mut R) -> PathPoint {
+        self.sample(rng.gen::<f32>() * self.length())
+    }
+
+    /// 段数，闭合路径最后连回起点的一段也算
+    #[must_use]
+    pub const fn segment_count(&self) -> usize {
+        self.segment_ends.len()
+    }
+
+    /// 第 `index` 段的起点和终点离路径起点的距离
+    fn segment_range(&self, index: usize) -> Option<(f32, f32)> {
+        let end = *self.segment_ends.get(index)?;
+        let begin = index.checked_sub(1).map_or(0.0, |i| self.segment_ends[i]);
+        Some((begin, end))
+    }
+
+    /// `segments` 里的几段首尾拼起来，离开头 `offset` 远的点，`segments` 为空时用整条路径
+    ///
+    /// `offset` 从 0 到 [`Path::segments_length`]，不存在的段被忽略
+    #[must_use]
+    pub fn sample_segments(&self, segments: &[usize], offset: f32) -> PathPoint {
+        if segments.is_empty() {
+            return self.sample(offset);
+        }
+        let mut offset = offset.max(0.0);
+        let mut last = None;
+        for (begin, end) in segments.iter().filter_map(|&i| self.segment_range(i)) {
+            if offset < end - begin {
+                return self.sample(begin + offset);
+            }
+            offset -= end - begin;
+            last = Some(end);
+        }
+        self.sample(last.unwrap_or_default())
+    }
+
+    /// `segments` 里几段的总长，为空时是整条路径的长度
+    #[must_use]
+    pub fn segments_length(&self, segments: &[usize]) -> f32 {
+        if segments.is_empty() {
+            return self.length();
+        }
+        segments
+            .iter()
+            .filter_map(|&i| self.segment_range(i))
+            .map(|(begin, end)| end - begin)
+            .sum()
+    }
+
+    /// 点是否在这个闭合的凸多边形里面，边上的也算
+    #[must_use]
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.edges()
+            .all(|(begin, end)| (point - begin).dot(self.outward(end - begin)) <= 0.0)
+    }
+
+    /// 闭合的凸多边形里离 `point` 最近的点
+    #[must_use]
+    pub fn clamp(&self, point: Vec2) -> Vec2 {
+        if self.contains(point) {
+            return point;
+        }
+        self.edges()
+            .map(|(begin, end)| {
+                let diff = end - begin;
+                let t = ((point - begin).dot(diff) / diff.length_squared()).clamp(0.0, 1.0);
+                begin + diff * t
+            })
+            .min_by(|a, b| {
+                a.distance_squared(point)
+                    .total_cmp(&b.distance_squared(point))
+            })
+            .unwrap_or(point)
+    }
+
+    /// 闭合的凸多边形每条边向外平移 `margin` 之后的多边形，长度为零的边不动
+    #[must_use]
+    pub fn expand(&self, margin: f32) -> Self {
+        let vertices = self.vertices();
+        let count = vertices.len();
+        let normal = |begin: Vec2, end: Vec2| self.outward(end - begin).normalize_or_zero();
+        let expanded: Vec<_> = (0..count)
+            .map(|i| {
+                let point = vertices[i];
+                let before = normal(vertices[(i + count - 1) % count], point);
+                let after = normal(point, vertices[(i + 1) % count]);
+                // 两条相邻的边平移之后的交点
+                point + (before + after) * margin / (1.0 + before.dot(after))
+            })
+            .collect();
+        Self::polygon(&expanded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    /// 200 x 100 的矩形，四条边依次长 200、100、200、100
+    fn rect() -> Path {
+        Path::rect(Vec2::new(100.0, 50.0))
+    }
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.abs_diff_eq(b, EPSILON), "{a} != {b}");
+    }
+
+    #[test]
+    fn sample_walks_a_closed_path_and_wraps() {
+        let path = rect();
+        assert!((path.length() - 600.0).abs() < EPSILON);
+        let point = path.sample(50.0);
+        assert_near(point.position, Vec2::new(-50.0, 50.0));
+        assert_near(point.tangent, Vec2::X);
+        assert_near(point.normal, Vec2::Y);
+        assert_near(path.sample(650.0).position, Vec2::new(-50.0, 50.0));
+        assert_near(path.sample(-50.0).position, Vec2::new(-100.0, 0.0));
+    }
+
+    #[test]
+    fn sample_clamps_an_open_path() {
+        let path = Path::new(Vec2::ZERO).line_to(Vec2::new(10.0, 0.0));
+        assert_near(path.sample(-5.0).position, Vec2::ZERO);
+        assert_near(path.sample(20.0).position, Vec2::new(10.0, 0.0));
+    }
+
+    #[test]
+    fn sample_follows_an_arc() {
+        let path = Path::new(Vec2::new(10.0, 0.0)).arc(Vec2::ZERO, PI);
+        for i in 0..=10 {
+            #[allow(clippy::cast_precision_loss)]
+            let distance = path.length() * i as f32 / 10.0;
+            assert!((path.sample(distance).position.length() - 10.0).abs() < 0.1);
+        }
+        assert_near(path.sample(path.length()).position, Vec2::new(-10.0, 0.0));
+    }
+
+    #[test]
+    fn segments_length_sums_the_chosen_segments() {
+        let path = rect();
+        assert_eq!(path.segment_count(), 4);
+        assert!((path.segments_length(&[]) - 600.0).abs() < EPSILON);
+        assert!((path.segments_length(&[0, 2]) - 400.0).abs() < EPSILON);
+        assert!((path.segments_length(&[1]) - 100.0).abs() < EPSILON);
+        assert!(path.segments_length(&[9]).abs() < EPSILON);
+    }
+
+    #[test]
+    fn sample_segments_joins_the_chosen_segments() {
+        let path = rect();
+        // 第 1 段是右边，从上往下
+        assert_near(
+            path.sample_segments(&[1], 25.0).position,
+            Vec2::new(100.0, 25.0),
+        );
+        // 跳过右边之后接着是下边，从右往左
+        assert_near(
+            path.sample_segments(&[1, 2], 150.0).position,
+            Vec2::new(50.0, -50.0),
+        );
+    }
+
+    #[test]
+    fn clamp_moves_outside_points_onto_the_nearest_edge() {
+        let path = rect();
+        assert_near(path.clamp(Vec2::new(10.0, 20.0)), Vec2::new(10.0, 20.0));
+        assert_near(path.clamp(Vec2::new(300.0, 0.0)), Vec2::new(100.0, 0.0));
+        assert_near(path.clamp(Vec2::new(0.0, -80.0)), Vec2::new(0.0, -50.0));
+        assert_near(path.clamp(Vec2::new(150.0, 90.0)), Vec2::new(100.0, 50.0));
+    }
+
+    #[test]
+    fn expand_moves_every_edge_outwards() {
+        let expanded = rect().expand(10.0);
+        let expected = Path::rect(Vec2::new(110.0, 60.0));
+        assert_eq!(expanded.vertices().len(), expected.vertices().len());
+        for (&a, &b) in expanded.vertices().iter().zip(expected.vertices()) {
+            assert_near(a, b);
+        }
+        assert!(expanded.contains(Vec2::new(105.0, 55.0)));
+        assert!(!rect().contains(Vec2::new(105.0, 55.0)));
+    }
+
+    #[test]
+    fn degenerate_paths_do_not_panic() {
+        let point = Path::new(Vec2::new(1.0, 2.0));
+        assert_near(point.sample(5.0).position, Vec2::new(1.0, 2.0));
+        assert!(point.validate().is_err());
+        assert!(Path::polygon(&[]).validate().is_err());
+        let expanded = Path::rect(Vec2::ZERO).expand(10.0);
+        assert!(expanded.vertices().iter().all(|v| v.is_finite()));
     }
 
-    pub const fn rotation(&self) -> Vec2 {
-        self.rotation
+    #[test]
+    fn deserializing_rejects_degenerate_paths() {
+        assert!(ron::from_str::<Path>("(start: (0.0, 0.0), segments: [])").is_err());
+        assert!(
+            ron::from_str::<Path>("(start: (0.0, 0.0), segments: [Line((0.0, 0.0))])").is_err()
+        );
+        assert!(
+            ron::from_str::<Path>("(start: (0.0, 0.0), segments: [Line((10.0, 0.0))])").is_ok()
+        );
     }
 }
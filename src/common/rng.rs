@@ -0,0 +1,76 @@
+use std::ops::{Deref, DerefMut};
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::common::arg_value;
+use crate::GameState;
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RngSeed>()
+            .init_resource::<GameRng>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reseed_rng));
+    }
+}
+
+/// 配置的随机数种子，为 `None` 时每局随机选一个
+#[derive(Default, Clone, Copy, Debug)]
+pub struct RngSeed(pub Option<u64>);
+
+impl RngSeed {
+    /// 依次从命令行参数 `--seed <u64>` 和环境变量 `DODGE_SEED` 读取
+    #[must_use]
+    pub fn from_args_or_env() -> Self {
+        let seed = arg_value("--seed")
+            .or_else(|| std::env::var("DODGE_SEED").ok())
+            .and_then(|s| s.parse().ok());
+        Self(seed)
+    }
+}
+
+/// 游戏中所有随机数的唯一来源，同一个种子得到完全相同的一局
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Deref for GameRng {
+    type Target = StdRng;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rng
+    }
+}
+impl DerefMut for GameRng {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.rng
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// 本局使用的种子
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl FromWorld for GameRng {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(world.resource::<RngSeed>().0.unwrap_or_else(rand::random))
+    }
+}
+
+pub fn reseed_rng(seed: Res<RngSeed>, mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(seed.0.unwrap_or_else(rand::random));
+}
@@ -0,0 +1,254 @@
+//! 固定步长的游戏逻辑
+//!
+//! 游戏逻辑只在 [`GameTick`] 这个子 schedule 里运行，每个 tick 前进 [`FixedTick::delta`]，
+//! 和渲染帧率无关。渲染时用 [`Interpolated`] 在前后两个 tick 之间插值
+
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use bevy::ecs::schedule::{IntoSystemDescriptor, ShouldRun};
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::common::arg_value;
+use crate::GameState;
+
+pub struct TickPlugin;
+
+impl Plugin for TickPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<FixedTick>() {
+            app.init_resource::<FixedTick>();
+        }
+        app.add_stage_before(
+            CoreStage::Update,
+            GameTick,
+            Schedule::default()
+                .with_run_criteria(run_tick)
+                .with_stage(TickStage::PreUpdate, SystemStage::parallel())
+                .with_stage(TickStage::Update, SystemStage::parallel())
+                .with_stage(TickStage::PostUpdate, SystemStage::parallel()),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_tick))
+        .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(stop_tick))
+        .add_system_set(SystemSet::on_pause(GameState::Playing).with_system(stop_tick))
+        .add_system_set(SystemSet::on_resume(GameState::Playing).with_system(start_tick))
+        .add_tick_system(TickStage::PreUpdate, begin_interpolation)
+        .add_tick_system(TickStage::PostUpdate, end_interpolation)
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            interpolate.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// 游戏逻辑所在的子 schedule，进入 [`GameState::Playing`] 后开始运行，暂停时停止
+#[derive(StageLabel)]
+pub struct GameTick;
+
+/// [`GameTick`] 内部的 stage，物理引擎的 stage 位于 `Update` 和 `PostUpdate` 之间
+#[derive(StageLabel, Clone, Copy)]
+pub enum TickStage {
+    PreUpdate,
+    Update,
+    PostUpdate,
+}
+
+/// 固定步长的时钟
+pub struct FixedTick {
+    tick_rate: NonZeroU32,
+    delta: Duration,
+    accumulator: Duration,
+    running: bool,
+    looping: bool,
+    overstep: f32,
+    time_scale: f32,
+}
+
+impl Default for FixedTick {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TICK_RATE)
+    }
+}
+
+impl FixedTick {
+    /// 默认每秒 60 次
+    pub const DEFAULT_TICK_RATE: NonZeroU32 = match NonZeroU32::new(60) {
+        Some(rate) => rate,
+        None => unreachable!(),
+    };
+    /// 一帧最多追赶的时间，避免卡顿之后一次跑太多 tick
+    const MAX_CATCH_UP: Duration = Duration::from_millis(250);
+
+    #[must_use]
+    pub fn new(tick_rate: NonZeroU32) -> Self {
+        Self {
+            tick_rate,
+            delta: Duration::from_secs(1) / tick_rate.get(),
+            accumulator: Duration::ZERO,
+            running: false,
+            looping: false,
+            overstep: 1.0,
+            time_scale: 1.0,
+        }
+    }
+
+    /// 依次从命令行参数 `--tick-rate <u32>` 和环境变量 `DODGE_TICK_RATE` 读取，默认每秒 60 次
+    ///
+    /// 0 和无法解析的值被忽略
+    #[must_use]
+    pub fn from_args_or_env() -> Self {
+        let parse = |name: &str, s: String| {
+            let rate = s.parse::<NonZeroU32>().ok();
+            if rate.is_none() {
+                warn!("ignoring invalid tick rate {s:?} from {name}");
+            }
+            rate
+        };
+        arg_value("--tick-rate")
+            .and_then(|s| parse("--tick-rate", s))
+            .or_else(|| {
+                std::env::var("DODGE_TICK_RATE")
+                    .ok()
+                    .and_then(|s| parse("DODGE_TICK_RATE", s))
+            })
+            .map_or_else(Self::default, Self::new)
+    }
+
+    /// 每秒的 tick 数
+    #[must_use]
+    pub const fn tick_rate(&self) -> NonZeroU32 {
+        self.tick_rate
+    }
+
+    pub fn set_tick_rate(&mut self, tick_rate: NonZeroU32) {
+        self.tick_rate = tick_rate;
+        self.delta = Duration::from_secs(1) / tick_rate.get();
+    }
+
+    /// 游戏时间相对真实时间的倍速，无头回放时可以设得很大
+    pub const fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale;
+    }
+
+    #[must_use]
+    pub const fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    #[must_use]
+    pub const fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// 距离上一个 tick 过去了多少个步长，用于插值，范围 `0..=1`
+    #[must_use]
+    pub const fn overstep(&self) -> f32 {
+        self.overstep
+    }
+
+    pub const fn start(&mut self) {
+        self.running = true;
+        self.accumulator = Duration::ZERO;
+    }
+
+    /// 停止运行，这一帧剩下的 tick 也不再执行，比如游戏结束之后
+    pub const fn stop(&mut self) {
+        self.running = false;
+        self.accumulator = Duration::ZERO;
+    }
+}
+
+pub fn start_tick(mut tick: ResMut<FixedTick>) {
+    tick.start();
+}
+
+fn stop_tick(mut tick: ResMut<FixedTick>) {
+    tick.stop();
+}
+
+fn run_tick(time: Res<Time>, mut tick: ResMut<FixedTick>) -> ShouldRun {
+    let tick = &mut *tick;
+    if !tick.running {
+        tick.looping = false;
+        tick.overstep = 1.0;
+        return ShouldRun::No;
+    }
+    if !tick.looping {
+        let elapsed = time.delta().mul_f32(tick.time_scale);
+        let max = FixedTick::MAX_CATCH_UP.mul_f32(tick.time_scale);
+        tick.accumulator = (tick.accumulator + elapsed).min(max);
+    }
+    if tick.accumulator >= tick.delta {
+        tick.accumulator -= tick.delta;
+        tick.looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        tick.looping = false;
+        tick.overstep = tick.accumulator.as_secs_f32() / tick.delta.as_secs_f32();
+        ShouldRun::No
+    }
+}
+
+pub trait TickAppExt {
+    fn add_tick_system<Params>(
+        &mut self,
+        stage: TickStage,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> &mut Self;
+
+    fn add_tick_system_set(&mut self, stage: TickStage, system_set: SystemSet) -> &mut Self;
+}
+
+impl TickAppExt for App {
+    fn add_tick_system<Params>(
+        &mut self,
+        stage: TickStage,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> &mut Self {
+        self.schedule.stage(GameTick, |schedule: &mut Schedule| {
+            schedule.add_system_to_stage(stage, system)
+        });
+        self
+    }
+
+    fn add_tick_system_set(&mut self, stage: TickStage, system_set: SystemSet) -> &mut Self {
+        self.schedule.stage(GameTick, |schedule: &mut Schedule| {
+            schedule.add_system_set_to_stage(stage, system_set)
+        });
+        self
+    }
+}
+
+/// 渲染时在前后两个 tick 的位置之间插值
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous: Option<Vec3>,
+    current: Vec3,
+}
+
+/// tick 开始时撤销插值，恢复成上一个 tick 的位置
+pub fn begin_interpolation(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut transform, mut interpolated) in &mut query {
+        if interpolated.previous.is_some() {
+            transform.translation = interpolated.current;
+            interpolated.previous = Some(interpolated.current);
+        }
+    }
+}
+
+fn end_interpolation(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.current = transform.translation;
+        interpolated.previous.get_or_insert(transform.translation);
+    }
+}
+
+fn interpolate(tick: Res<FixedTick>, mut query: Query<(&mut Transform, &Interpolated)>) {
+    let t = tick.overstep();
+    for (mut transform, interpolated) in &mut query {
+        if let Some(previous) = interpolated.previous {
+            transform.translation = previous.lerp(interpolated.current, t);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! 游戏的可调参数，来自 `assets/game.config.ron`
+//!
+//! 加载时读入 [`GameConfig`] 资源，开启 `dev` feature 时修改文件会热重载
+
+use std::path::Path;
+use std::{fs, io};
+
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::ArenaConfig;
+use crate::difficulty::DifficultyPresets;
+use crate::enemy::EnemyArchetypes;
+use crate::lives::LivesConfig;
+use crate::loading::ConfigAssets;
+use crate::pickup::PickupConfig;
+use crate::score::ScoreConfig;
+use crate::sfx::SoundCues;
+
+/// 注册配置文件的加载器，并在配置变化时同步到 [`GameConfig`] 资源
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<GameConfig>()
+            .init_asset_loader::<ConfigLoader>()
+            .add_system(apply_config);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, TypeUuid)]
+#[uuid = "6ab85fba-7f93-498d-95b0-028fb1258b3a"]
+#[serde(default)]
+pub struct GameConfig {
+    /// 游戏画面的逻辑大小，也是默认的窗口大小，窗口比例不同时画面两侧留黑边
+    pub window: Vec2,
+    /// 游戏开始时的游戏区域，见 [`Arena`](crate::arena::Arena)
+    pub arena: ArenaConfig,
+    pub player: PlayerConfig,
+    /// 每种难度的生命数，以及少一条命之后的无敌时间和击退
+    pub lives: LivesConfig,
+    /// 每种难度的曲线
+    pub difficulty: DifficultyPresets,
+    /// 每种敌人的参数
+    pub enemies: EnemyArchetypes,
+    /// 道具出现的频率和每种效果的参数
+    pub pickups: PickupConfig,
+    /// 存活时间、擦身而过和捡道具的得分
+    pub score: ScoreConfig,
+    /// 每个音效的文件和参数
+    pub sounds: SoundCues,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerConfig {
+    /// 每秒移动的像素
+    pub speed: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            window: Vec2::new(400.0, 720.0),
+            arena: ArenaConfig::default(),
+            player: PlayerConfig::default(),
+            lives: LivesConfig::default(),
+            difficulty: DifficultyPresets::default(),
+            enemies: EnemyArchetypes::default(),
+            pickups: PickupConfig::default(),
+            score: ScoreConfig::default(),
+            sounds: SoundCues::default(),
+        }
+    }
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self { speed: 400.0 }
+    }
+}
+
+impl GameConfig {
+    /// 不经过资源系统直接读取配置文件，给无头模式用
+    ///
+    /// # Errors
+    ///
+    /// 文件读取失败、格式不对或者 [`validate`](Self::validate) 不通过
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let s = fs::read_to_string(path)?;
+        let config: Self =
+            ron::from_str(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        config
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(config)
+    }
+
+    /// 检查会让游戏逻辑出错的数值，比如反过来的速度范围
+    ///
+    /// # Errors
+    ///
+    /// 返回第一个无效的值
+    pub fn validate(&self) -> Result<(), String> {
+        self.arena.validate()?;
+        self.difficulty.validate()?;
+        self.enemies.validate()?;
+        self.pickups.validate()
+    }
+
+    /// 影响游戏逻辑的那部分配置的指纹，录像里记下来，回放时发现配置变了
+    ///
+    /// 对序列化之后的文本做 FNV-1a，不随编译器版本变化，窗口大小和音效不算在内
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        let gameplay = (
+            &self.arena,
+            &self.player,
+            &self.lives,
+            &self.difficulty,
+            &self.enemies,
+            &self.pickups,
+            &self.score,
+        );
+        ron::to_string(&gameplay)
+            .unwrap_or_default()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+}
+
+#[derive(Default)]
+struct ConfigLoader;
+
+impl AssetLoader for ConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let config: GameConfig = ron::de::from_bytes(bytes)?;
+            // 热重载时无效的配置不会替换掉正在使用的
+            config.validate().map_err(anyhow::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["config.ron"]
+    }
+}
+
+/// 加载完成和热重载之后，把配置文件的内容复制到 [`GameConfig`] 资源
+fn apply_config(
+    mut config: ResMut<GameConfig>,
+    mut events: EventReader<AssetEvent<GameConfig>>,
+    assets: Res<Assets<GameConfig>>,
+    handles: Option<Res<ConfigAssets>>,
+) {
+    let Some(handles) = handles else {
+        return;
+    };
+    let modified = events
+        .iter()
+        .any(|e| matches!(e, AssetEvent::Modified { handle } if *handle == handles.game));
+    if !handles.is_added() && !modified {
+        return;
+    }
+    if let Some(loaded) = assets.get(&handles.game) {
+        *config = loaded.clone();
+    }
+}
@@ -0,0 +1,277 @@
+//! 修改按键绑定的界面：点击一个格子后按下新的按键
+//!
+//! Backspace 清空这个格子，Escape 取消
+
+use bevy::prelude::*;
+
+use crate::bindings::{Action, ActionInput, BindingSlot, Bindings};
+use crate::common::clear_entities;
+use crate::focus::Focus;
+use crate::letterbox::UiRoot;
+use crate::loading::FontAssets;
+use crate::menu::ButtonColors;
+use crate::GameState;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Controls)
+                    .with_system(click_controls_button)
+                    .with_system(capture_binding.after(click_controls_button))
+                    .with_system(leave_controls.after(capture_binding))
+                    .with_system(show_controls.after(leave_controls))
+                    .with_system(lock_focus.after(leave_controls)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Controls)
+                    .with_system(clear_entities::<ControlsScreen>)
+                    .with_system(cancel_rebinding),
+            );
+    }
+}
+
+/// 正在等待新按键的格子
+#[derive(Default)]
+struct Rebinding(Option<(Action, BindingSlot)>);
+
+#[derive(Component)]
+struct ControlsScreen;
+
+#[derive(Component, Clone, Copy)]
+enum ControlsButton {
+    Slot(Action, BindingSlot),
+    Reset,
+    Back,
+}
+
+const SLOTS: [BindingSlot; 3] = [
+    BindingSlot::Key(0),
+    BindingSlot::Key(1),
+    BindingSlot::Button,
+];
+
+fn cancel_rebinding(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
+#[allow(clippy::type_complexity)]
+fn click_controls_button(
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<State<GameState>>,
+    mut bindings: ResMut<Bindings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut interaction_query: Query<
+        (&Interaction, &mut UiColor, &ControlsButton),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, mut color, &button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => match button {
+                ControlsButton::Slot(action, slot) => rebinding.0 = Some((action, slot)),
+                ControlsButton::Reset => {
+                    *bindings = Bindings::default();
+                    rebinding.0 = None;
+                }
+                ControlsButton::Back => {
+                    if let Err(e) = state.set(GameState::Menu) {
+                        debug!("ignoring back click: {e:?}");
+                    }
+                }
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        }
+    }
+}
+
+fn capture_binding(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut bindings: ResMut<Bindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    let Some((action, slot)) = rebinding.0 else {
+        return;
+    };
+    let key = keys.get_just_pressed().next().copied();
+    let button = buttons.get_just_pressed().next().copied();
+    if let Some(key) = key {
+        keys.clear_just_pressed(key);
+        match (key, slot) {
+            (KeyCode::Escape, _) => {}
+            (KeyCode::Back, BindingSlot::Button) => bindings.set_button(action, None),
+            (KeyCode::Back, _) => bindings.set_key(action, slot, None),
+            (_, BindingSlot::Key(_)) => bindings.set_key(action, slot, Some(key)),
+            (_, BindingSlot::Button) => return,
+        }
+        rebinding.0 = None;
+    } else if let Some(button) = button {
+        buttons.clear_just_pressed(button);
+        if slot == BindingSlot::Button {
+            bindings.set_button(action, Some(button.button_type));
+            rebinding.0 = None;
+        }
+    }
+}
+
+fn leave_controls(
+    mut actions: ActionInput,
+    rebinding: Res<Rebinding>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if rebinding.0.is_none() && actions.take(Action::Back) {
+        if let Err(e) = state.set(GameState::Menu) {
+            debug!("not leaving controls: {e:?}");
+        }
+    }
+}
+
+/// 等待新按键时方向键和确认键也要能绑定，不能拿来移动焦点
+fn lock_focus(rebinding: Res<Rebinding>, mut focus: ResMut<Focus>) {
+    focus.locked = rebinding.0.is_some();
+}
+
+fn slot_label(bindings: &Bindings, action: Action, slot: BindingSlot) -> String {
+    let binding = bindings.get(action);
+    let label = match slot {
+        BindingSlot::Key(i) => binding.keys[i].map(|key| format!("{key:?}")),
+        BindingSlot::Button => binding.button.map(|button| format!("{button:?}")),
+    };
+    label.unwrap_or_else(|| "-".to_owned())
+}
+
+fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            margin: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }
+}
+
+fn button(width: f32, color: UiColor) -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(width), Val::Px(34.0)),
+            margin: UiRect::all(Val::Px(3.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color,
+        ..default()
+    }
+}
+
+fn show_controls(
+    mut commands: Commands,
+    ui_root: Res<UiRoot>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    bindings: Res<Bindings>,
+    rebinding: Res<Rebinding>,
+    screen: Query<Entity, With<ControlsScreen>>,
+) {
+    if !screen.is_empty() && !bindings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+    let text = |value: String, font_size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.xolonium_regular.clone(),
+                font_size,
+                color,
+            },
+        )
+    };
+    let conflict = Action::ALL
+        .into_iter()
+        .find_map(|action| Some((action, bindings.conflicts(action).next()?)));
+    let message = match (rebinding.0, conflict) {
+        (Some(_), _) => "Press a key or button\nBackspace clears, Escape cancels".to_owned(),
+        (None, Some((a, b))) => format!("{} and {} share an input", a.name(), b.name()),
+        (None, None) => String::new(),
+    };
+    let entity = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(ControlsScreen)
+        .with_children(|parent| {
+            parent.spawn_bundle(text("Controls".to_owned(), 40.0, Color::WHITE));
+            for action in Action::ALL {
+                let label_color = if bindings.conflicts(action).next().is_some() {
+                    Color::RED
+                } else {
+                    Color::WHITE
+                };
+                parent.spawn_bundle(row()).with_children(|parent| {
+                    parent.spawn_bundle(
+                        text(action.name().to_owned(), 18.0, label_color).with_style(Style {
+                            size: Size::new(Val::Px(80.0), Val::Auto),
+                            ..default()
+                        }),
+                    );
+                    for slot in SLOTS {
+                        let capturing = rebinding.0 == Some((action, slot));
+                        let (label, color) = if capturing {
+                            ("...".to_owned(), button_colors.selected)
+                        } else {
+                            (slot_label(&bindings, action, slot), button_colors.normal)
+                        };
+                        parent
+                            .spawn_bundle(button(94.0, color))
+                            .insert(ControlsButton::Slot(action, slot))
+                            .with_children(|parent| {
+                                parent.spawn_bundle(text(label, 14.0, Color::rgb(0.9, 0.9, 0.9)));
+                            });
+                    }
+                });
+            }
+            parent.spawn_bundle(text(message, 14.0, Color::GOLD));
+            parent.spawn_bundle(row()).with_children(|parent| {
+                for (action, label) in [
+                    (ControlsButton::Reset, "Reset"),
+                    (ControlsButton::Back, "Back"),
+                ] {
+                    parent
+                        .spawn_bundle(button(140.0, button_colors.normal))
+                        .insert(action)
+                        .with_children(|parent| {
+                            parent.spawn_bundle(text(
+                                label.to_owned(),
+                                24.0,
+                                Color::rgb(0.9, 0.9, 0.9),
+                            ));
+                        });
+                }
+            });
+        })
+        .id();
+    ui_root.add(&mut commands, entity);
+}
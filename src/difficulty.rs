@@ -0,0 +1,245 @@
+//! 难度：随着存活时间增加，敌人生成得更快、速度更高、同屏数量更多
+//!
+//! 每种难度对应配置文件里的一条曲线，曲线由若干关键点组成，关键点之间线性插值
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::check_range;
+use crate::common::tick::{TickAppExt, TickStage};
+use crate::config::GameConfig;
+use crate::score::Score;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>()
+            .init_resource::<DifficultyLevel>()
+            .add_tick_system(TickStage::PreUpdate, update_difficulty_level);
+    }
+}
+
+/// 菜单里选择的难度
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+}
+
+/// 曲线上的一个关键点，`time` 是存活的秒数
+///
+/// 作为资源时表示当前这个 tick 的难度
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DifficultyLevel {
+    pub time: f32,
+    /// 生成敌人的间隔，单位秒
+    pub spawn_interval: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// 同屏最多的敌人数量
+    pub max_enemies: u32,
+}
+
+impl Default for DifficultyLevel {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            spawn_interval: 0.5,
+            min_speed: 100.0,
+            max_speed: 300.0,
+            max_enemies: 15,
+        }
+    }
+}
+
+impl DifficultyLevel {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| (b - a).mul_add(t, a);
+        Self {
+            time: lerp(self.time, other.time),
+            spawn_interval: lerp(self.spawn_interval, other.spawn_interval),
+            min_speed: lerp(self.min_speed, other.min_speed),
+            max_speed: lerp(self.max_speed, other.max_speed),
+            max_enemies: lerp(self.max_enemies as f32, other.max_enemies as f32).round() as u32,
+        }
+    }
+}
+
+/// 按 `time` 从小到大排列的关键点
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DifficultyCurve(pub Vec<DifficultyLevel>);
+
+impl DifficultyCurve {
+    /// 存活 `time` 秒时的难度，超出曲线两端时取端点的值
+    #[must_use]
+    pub fn sample(&self, time: f32) -> DifficultyLevel {
+        let i = self.0.partition_point(|level| level.time <= time);
+        match (i.checked_sub(1).map(|i| &self.0[i]), self.0.get(i)) {
+            (Some(prev), Some(next)) => {
+                prev.lerp(next, (time - prev.time) / (next.time - prev.time))
+            }
+            (Some(level), None) | (None, Some(level)) => *level,
+            (None, None) => DifficultyLevel::default(),
+        }
+    }
+
+    /// 关键点按 `time` 严格递增，速度范围有效，生成间隔为正
+    ///
+    /// # Errors
+    ///
+    /// 返回第一个不符合的关键点，`name` 用在错误信息里
+    pub fn validate(&self, name: &str) -> Result<(), String> {
+        let mut previous = f32::NEG_INFINITY;
+        for (i, level) in self.0.iter().enumerate() {
+            if !(level.time.is_finite() && level.time > previous) {
+                return Err(format!("{name}[{i}]: time must increase along the curve"));
+            }
+            previous = level.time;
+            if !(level.spawn_interval.is_finite() && level.spawn_interval > 0.0) {
+                return Err(format!("{name}[{i}]: spawn_interval must be positive"));
+            }
+            check_range(
+                &format!("{name}[{i}] speed"),
+                level.min_speed,
+                level.max_speed,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 三种难度各自的曲线
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyPresets {
+    pub easy: DifficultyCurve,
+    pub normal: DifficultyCurve,
+    pub hard: DifficultyCurve,
+}
+
+impl Default for DifficultyPresets {
+    fn default() -> Self {
+        let level = DifficultyLevel::default();
+        Self {
+            easy: DifficultyCurve(vec![level]),
+            normal: DifficultyCurve(vec![level]),
+            hard: DifficultyCurve(vec![level]),
+        }
+    }
+}
+
+impl DifficultyPresets {
+    #[must_use]
+    pub const fn get(&self, difficulty: Difficulty) -> &DifficultyCurve {
+        match difficulty {
+            Difficulty::Easy => &self.easy,
+            Difficulty::Normal => &self.normal,
+            Difficulty::Hard => &self.hard,
+        }
+    }
+
+    /// # Errors
+    ///
+    /// 某条曲线无效
+    pub fn validate(&self) -> Result<(), String> {
+        self.easy.validate("difficulty.easy")?;
+        self.normal.validate("difficulty.normal")?;
+        self.hard.validate("difficulty.hard")
+    }
+}
+
+/// 根据难度曲线和当前得分算出这个 tick 的难度
+fn update_difficulty_level(
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    score: Res<Score>,
+    mut level: ResMut<DifficultyLevel>,
+) {
+    *level = config.difficulty.get(*difficulty).sample(score.time);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(time: f32, spawn_interval: f32, max_enemies: u32) -> DifficultyLevel {
+        DifficultyLevel {
+            time,
+            spawn_interval,
+            min_speed: 100.0,
+            max_speed: 200.0,
+            max_enemies,
+        }
+    }
+
+    fn curve() -> DifficultyCurve {
+        DifficultyCurve(vec![level(0.0, 1.0, 5), level(10.0, 0.5, 10)])
+    }
+
+    #[test]
+    fn sample_interpolates_between_keys() {
+        let sampled = curve().sample(5.0);
+        assert!((sampled.spawn_interval - 0.75).abs() < 1e-6);
+        assert_eq!(sampled.max_enemies, 8);
+        assert!((sampled.time - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sample_holds_the_ends() {
+        let curve = curve();
+        assert!((curve.sample(-3.0).spawn_interval - 1.0).abs() < 1e-6);
+        assert!((curve.sample(10.0).spawn_interval - 0.5).abs() < 1e-6);
+        assert_eq!(curve.sample(100.0).max_enemies, 10);
+    }
+
+    #[test]
+    fn sample_of_an_empty_curve_is_the_default() {
+        let sampled = DifficultyCurve::default().sample(5.0);
+        let default = DifficultyLevel::default();
+        assert!((sampled.spawn_interval - default.spawn_interval).abs() < 1e-6);
+        assert_eq!(sampled.max_enemies, default.max_enemies);
+    }
+
+    #[test]
+    fn validate_accepts_the_defaults() {
+        assert!(DifficultyPresets::default().validate().is_ok());
+        assert!(curve().validate("curve").is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_bad_curves() {
+        let mut inverted = curve();
+        inverted.0[1].min_speed = 300.0;
+        assert!(inverted.validate("curve").is_err());
+
+        let mut unsorted = curve();
+        unsorted.0.reverse();
+        assert!(unsorted.validate("curve").is_err());
+
+        let mut zero_interval = curve();
+        zero_interval.0[0].spawn_interval = 0.0;
+        assert!(zero_interval.validate("curve").is_err());
+    }
+}
@@ -1,94 +1,295 @@
 use std::f32::consts::PI;
 
 use benimator::FrameRate;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::common::animation::{animate, Animation, AnimationState};
-use crate::common::clear_entities;
+use crate::arena::Arena;
+use crate::collision::CollisionRole;
+use crate::common::animation::{Animation, AnimationState};
+use crate::common::tick::{FixedTick, Interpolated, TickAppExt, TickStage};
+use crate::common::{check_range, clear_entities};
+use crate::config::GameConfig;
 use crate::loading::EnemyTextureAtlas;
-use crate::player::spawn_player;
-use crate::{GameState, ScreenPath};
+use crate::pickup::PowerUps;
+use crate::player::{move_player, spawn_player, Player};
+use crate::GameState;
 
 pub struct EnemyPlugin;
 
+/// 生成了一个敌人，`position` 是出现的位置
+pub struct EnemySpawnEvent {
+    pub position: Vec2,
+}
+
 #[derive(Component)]
 pub struct Enemy {
     direction: Vec2,
     speed: f32,
+    /// 出生以来经过的秒数
+    age: f32,
+}
+
+/// 敌人的种类，决定贴图和 [`EnemyArchetypes`] 里对应的参数
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum EnemyKind {
+    Fly,
+    Swim,
+    Walk,
 }
 
-#[derive(Deref, DerefMut)]
-struct EnemyTimer(Timer);
+impl EnemyKind {
+    pub const ALL: [EnemyKind; 3] = [EnemyKind::Fly, EnemyKind::Swim, EnemyKind::Walk];
+}
 
-impl Default for EnemyTimer {
+/// 敌人的移动方式
+#[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum EnemyMovement {
+    /// 沿直线匀速前进
+    Straight,
+    /// 在前进方向的两侧来回摆动
+    SineWave { amplitude: f32, frequency: f32 },
+    /// 不断加速，直到 `max_speed`
+    Accelerate { acceleration: f32, max_speed: f32 },
+    /// 慢慢转向玩家，`turn_rate` 单位是弧度每秒
+    Homing { turn_rate: f32 },
+}
+
+/// 一种敌人的参数
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnemyArchetype {
+    /// 生成时被选中的权重
+    pub weight: f32,
+    /// 速度在难度给出的速度上乘以这个范围里的随机数
+    pub min_speed_scale: f32,
+    pub max_speed_scale: f32,
+    /// 胶囊碰撞体中间直线部分的半长
+    pub capsule_half_length: f32,
+    pub capsule_radius: f32,
+    pub movement: EnemyMovement,
+}
+
+impl Default for EnemyArchetype {
     fn default() -> Self {
-        Self(Timer::from_seconds(0.5, true))
+        Self {
+            weight: 1.0,
+            min_speed_scale: 1.0,
+            max_speed_scale: 1.0,
+            capsule_half_length: 10.0,
+            capsule_radius: 40.0,
+            movement: EnemyMovement::Straight,
+        }
+    }
+}
+
+/// 每种敌人的参数
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnemyArchetypes {
+    pub fly: EnemyArchetype,
+    pub swim: EnemyArchetype,
+    pub walk: EnemyArchetype,
+}
+
+impl EnemyArchetypes {
+    #[must_use]
+    pub const fn get(&self, kind: EnemyKind) -> &EnemyArchetype {
+        match kind {
+            EnemyKind::Fly => &self.fly,
+            EnemyKind::Swim => &self.swim,
+            EnemyKind::Walk => &self.walk,
+        }
+    }
+
+    /// 每种敌人的速度倍数范围有效，碰撞体的大小不为负
+    ///
+    /// # Errors
+    ///
+    /// 返回第一个无效的值
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, archetype) in [
+            ("enemies.fly", &self.fly),
+            ("enemies.swim", &self.swim),
+            ("enemies.walk", &self.walk),
+        ] {
+            check_range(
+                &format!("{name} speed scale"),
+                archetype.min_speed_scale,
+                archetype.max_speed_scale,
+            )?;
+            if !(archetype.capsule_half_length >= 0.0 && archetype.capsule_radius >= 0.0) {
+                return Err(format!("{name}: capsule size must not be negative"));
+            }
+        }
+        Ok(())
+    }
+
+    /// 按权重随机选一种敌人，权重都为零时等概率选择
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> EnemyKind {
+        self.choose_from(&EnemyKind::ALL, rng)
+    }
+
+    /// 只在 `kinds` 里按权重选，`kinds` 为空时所有种类都可以
+    pub fn choose_from<R: Rng + ?Sized>(&self, kinds: &[EnemyKind], rng: &mut R) -> EnemyKind {
+        let kinds = if kinds.is_empty() {
+            &EnemyKind::ALL[..]
+        } else {
+            kinds
+        };
+        let weights = kinds.iter().map(|&kind| self.get(kind).weight.max(0.0));
+        match WeightedIndex::new(weights) {
+            Ok(index) => kinds[index.sample(rng)],
+            Err(_) => kinds[rng.gen_range(0..kinds.len())],
+        }
     }
 }
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<EnemyTimer>()
+        app.add_event::<EnemySpawnEvent>()
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing)
                     .with_system(clear_entities::<Enemy>.before(spawn_player)),
             )
-            .add_system_set(
-                SystemSet::on_update(GameState::Playing)
-                    .with_system(move_enemy.before(animate))
-                    .with_system(spawn_enemy),
-            );
+            // 追踪玩家的敌人要用玩家这个 tick 移动之后的位置
+            .add_tick_system(TickStage::Update, move_enemy.after(move_player));
     }
 }
 
-fn spawn_enemy(
-    time: Res<Time>,
-    mut timer: ResMut<EnemyTimer>,
+/// 给敌人加上贴图和动画，无头模式下不需要
+pub struct EnemySpritePlugin;
+
+impl Plugin for EnemySpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set_to_stage(
+            CoreStage::PostUpdate,
+            SystemSet::on_update(GameState::Playing).with_system(attach_enemy_sprite),
+        );
+    }
+}
+
+/// 生成敌人，敌人什么时候、从哪里出现由 [`WaveDirector`](crate::waves::WaveDirector) 决定
+#[derive(SystemParam)]
+pub struct EnemySpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    config: Res<'w, GameConfig>,
+    spawned: EventWriter<'w, 's, EnemySpawnEvent>,
+}
+
+impl EnemySpawner<'_, '_> {
+    #[must_use]
+    pub fn archetypes(&self) -> &EnemyArchetypes {
+        &self.config.enemies
+    }
+
+    /// 在 `position` 生成一个朝 `direction` 前进的敌人，速度在 `speed` 上再乘以这种敌人的随机倍数
+    pub fn spawn<R: Rng + ?Sized>(
+        &mut self,
+        kind: EnemyKind,
+        position: Vec2,
+        direction: Vec2,
+        speed: f32,
+        rng: &mut R,
+    ) {
+        let archetype = self.config.enemies.get(kind);
+        let speed = speed * rng.gen_range(archetype.min_speed_scale..=archetype.max_speed_scale);
+        self.spawned.send(EnemySpawnEvent { position });
+        self.commands
+            .spawn_bundle(TransformBundle::from_transform(Transform {
+                translation: position.extend(0.),
+                scale: Vec3::new(0.5, 0.5, 0.5),
+                rotation: Quat::from_rotation_z(Vec2::X.angle_between(direction)),
+            }))
+            .insert(Enemy {
+                direction,
+                speed,
+                age: 0.0,
+            })
+            .insert(kind)
+            .insert(archetype.movement)
+            .insert(Interpolated::default())
+            .insert(RigidBody::KinematicPositionBased)
+            .insert(Collider::capsule_x(
+                archetype.capsule_half_length,
+                archetype.capsule_radius,
+            ))
+            .insert(Sensor)
+            .insert_bundle(CollisionRole::Enemy.bundle());
+    }
+}
+
+fn attach_enemy_sprite(
     mut commands: Commands,
     enemy_assets: Res<EnemyTextureAtlas>,
-    path: Res<ScreenPath>,
+    query: Query<(Entity, &EnemyKind), Added<Enemy>>,
 ) {
-    timer.tick(time.delta());
-    if !timer.just_finished() {
-        return;
+    for (entity, &kind) in &query {
+        commands
+            .entity(entity)
+            .insert_bundle((
+                TextureAtlasSprite::default(),
+                enemy_assets.get(kind),
+                Visibility::default(),
+                ComputedVisibility::default(),
+            ))
+            .insert(Animation::from_indices(0..2, FrameRate::from_fps(3.0)))
+            .insert(AnimationState::default());
     }
-    let seg = path.random_segment();
-    let mut rng = rand::thread_rng();
-    let r = seg.rotation();
-    let direction = r.rotate(Vec2::from_angle(rng.gen_range(-PI * 3.0 / 4.0..-PI / 4.0)));
-    let speed = rng.gen_range(100.0..300.0);
-    commands
-        .spawn_bundle(SpriteSheetBundle {
-            transform: Transform {
-                translation: seg.random_point().extend(0.),
-                scale: Vec3::new(0.5, 0.5, 0.5),
-                rotation: Quat::from_rotation_z(Vec2::X.angle_between(direction)),
-            },
-            texture_atlas: enemy_assets.random(),
-            ..Default::default()
-        })
-        .insert(Animation::from_indices(0..2, FrameRate::from_fps(3.0)))
-        .insert(AnimationState::default())
-        .insert(Enemy { direction, speed })
-        .insert(RigidBody::Dynamic)
-        .insert(Collider::capsule_x(10.0, 40.0))
-        .insert(CollisionGroups::new(0b1, 0b10))
-        .insert(LockedAxes::ROTATION_LOCKED);
 }
 
 fn move_enemy(
     mut commands: Commands,
-    time: Res<Time>,
-    mut enemy_query: Query<(&mut Transform, &Enemy, Entity), With<Enemy>>,
+    tick: Res<FixedTick>,
+    arena: Res<Arena>,
+    power_ups: Res<PowerUps>,
+    player_query: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<(&mut Transform, &mut Enemy, &EnemyMovement, Entity), Without<Player>>,
 ) {
-    for (mut enemy_transform, enemy, entity) in &mut enemy_query {
-        enemy_transform.translation +=
-            enemy.speed * time.delta_seconds() * enemy.direction.extend(0.);
-        if !(-250.0..250.).contains(&enemy_transform.translation.x)
-            || !(-400.0..400.).contains(&enemy_transform.translation.y)
-        {
+    // 减速道具让敌人的时间变慢
+    let dt = tick.delta_seconds() * power_ups.enemy_time_scale();
+    let player = player_query
+        .get_single()
+        .ok()
+        .map(|t| t.translation.truncate());
+    for (mut enemy_transform, mut enemy, &movement, entity) in &mut enemy_query {
+        let mut offset = Vec2::ZERO;
+        match movement {
+            EnemyMovement::Straight => {}
+            EnemyMovement::SineWave {
+                amplitude,
+                frequency,
+            } => {
+                // 横向位移是 amplitude * sin(2π * frequency * age)，这里加上这个 tick 的增量
+                let phase = |age: f32| (2.0 * PI * frequency * age).sin() * amplitude;
+                offset = enemy.direction.perp() * (phase(enemy.age + dt) - phase(enemy.age));
+            }
+            EnemyMovement::Accelerate {
+                acceleration,
+                max_speed,
+            } => {
+                enemy.speed = acceleration.mul_add(dt, enemy.speed).min(max_speed);
+            }
+            EnemyMovement::Homing { turn_rate } => {
+                if let Some(player) = player {
+                    let target = player - enemy_transform.translation.truncate();
+                    let angle = enemy.direction.angle_between(target);
+                    if angle.is_finite() {
+                        let max_turn = turn_rate * dt;
+                        enemy.direction = Vec2::from_angle(angle.clamp(-max_turn, max_turn))
+                            .rotate(enemy.direction);
+                        enemy_transform.rotation =
+                            Quat::from_rotation_z(Vec2::X.angle_between(enemy.direction));
+                    }
+                }
+            }
+        }
+        enemy.age += dt;
+        enemy_transform.translation += (enemy.speed * dt * enemy.direction + offset).extend(0.);
+        if arena.is_outside(enemy_transform.translation.truncate()) {
             commands.entity(entity).despawn();
         }
     }
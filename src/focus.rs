@@ -0,0 +1,176 @@
+//! 用方向键和手柄十字键在按钮之间移动焦点，确认键按下获得焦点的按钮
+//!
+//! 对所有带 [`Button`] 的实体生效，按下时把 [`Interaction`] 设为 `Clicked`，
+//! 所以各个界面处理鼠标点击的系统不需要改动
+//!
+//! 获得焦点的按钮和鼠标悬停一样显示 [`ButtonColors::hovered`]
+
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+
+use crate::bindings::{Action, ActionInput};
+use crate::menu::ButtonColors;
+use crate::GameState;
+
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Focus>()
+            .add_system_to_stage(CoreStage::PreUpdate, navigate_focus.after(UiSystem::Focus))
+            .add_system_to_stage(CoreStage::PostUpdate, highlight_focus);
+    }
+}
+
+/// 当前获得焦点的按钮，切换状态时清空
+#[derive(Default)]
+pub struct Focus {
+    entity: Option<Entity>,
+    /// 获得焦点的按钮的位置，界面重建之后把焦点交给离这里最近的按钮
+    position: Option<Vec2>,
+    /// 上一帧用确认键按下的按钮，这一帧松开
+    pressed: Option<Entity>,
+    /// 为 true 时不响应方向键和确认键，比如正在输入名字或者等待新的按键绑定
+    pub locked: bool,
+}
+
+impl Focus {
+    #[must_use]
+    pub const fn entity(&self) -> Option<Entity> {
+        self.entity
+    }
+}
+
+const DIRECTIONS: [(Action, Vec2); 4] = [
+    (Action::MoveUp, Vec2::Y),
+    (Action::MoveDown, Vec2::NEG_Y),
+    (Action::MoveLeft, Vec2::NEG_X),
+    (Action::MoveRight, Vec2::X),
+];
+
+/// `direction` 方向上最近的按钮，偏离这个方向的距离算两倍
+fn next_in_direction(
+    from: Vec2,
+    direction: Vec2,
+    buttons: impl Iterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    buttons
+        .filter_map(|(entity, position)| {
+            let offset = position - from;
+            let along = offset.dot(direction);
+            (along > 1.0).then(|| {
+                (
+                    entity,
+                    2.0f32.mul_add(offset.perp_dot(direction).abs(), along),
+                )
+            })
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+/// 最上面一行里最左边的按钮
+fn first(buttons: impl Iterator<Item = (Entity, Vec2)>) -> Option<Entity> {
+    buttons
+        .max_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(b.x.total_cmp(&a.x)))
+        .map(|(entity, _)| entity)
+}
+
+fn nearest(to: Vec2, buttons: impl Iterator<Item = (Entity, Vec2)>) -> Option<Entity> {
+    buttons
+        .min_by(|(_, a), (_, b)| a.distance_squared(to).total_cmp(&b.distance_squared(to)))
+        .map(|(entity, _)| entity)
+}
+
+#[allow(clippy::type_complexity)]
+fn navigate_focus(
+    mut actions: ActionInput,
+    state: Res<State<GameState>>,
+    mut last_state: Local<Option<GameState>>,
+    mut focus: ResMut<Focus>,
+    mut buttons: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut Interaction,
+            ChangeTrackers<Interaction>,
+        ),
+        With<Button>,
+    >,
+) {
+    if let Some(Ok((_, _, mut interaction, _))) = focus.pressed.take().map(|e| buttons.get_mut(e)) {
+        if *interaction == Interaction::Clicked {
+            *interaction = Interaction::None;
+        }
+    }
+    // 状态驱动每帧都会可变借用 `State`，所以不能用 `is_changed()` 判断
+    if last_state.as_ref() != Some(state.current()) {
+        *last_state = Some(state.current().clone());
+        *focus = Focus::default();
+    }
+    let positions = || {
+        buttons
+            .iter()
+            .map(|(entity, transform, ..)| (entity, transform.translation().truncate()))
+    };
+    let just_pressed = DIRECTIONS
+        .into_iter()
+        .find(|&(action, _)| !focus.locked && actions.just_pressed(action))
+        .map(|(_, direction)| direction);
+    // 鼠标刚移到的按钮优先
+    let hovered = buttons
+        .iter()
+        .find(|(_, _, interaction, tracker)| {
+            tracker.is_changed() && **interaction != Interaction::None
+        })
+        .map(|(entity, ..)| entity);
+    let current = focus
+        .entity
+        .and_then(|entity| buttons.get(entity).ok())
+        .map(|(entity, transform, ..)| (entity, transform.translation().truncate()));
+    let next = match (hovered, current, just_pressed) {
+        (Some(entity), ..) | (None, Some((entity, _)), None) => Some(entity),
+        (None, Some((entity, from)), Some(direction)) => {
+            next_in_direction(from, direction, positions()).or(Some(entity))
+        }
+        // 按钮被重建了
+        (None, None, _) if focus.position.is_some() => focus
+            .position
+            .and_then(|position| nearest(position, positions())),
+        (None, None, Some(_)) => first(positions()),
+        (None, None, None) => None,
+    };
+    if next != focus.entity {
+        // 让原来的按钮按自己的状态重新设置颜色
+        if let Some(Ok((_, _, mut interaction, _))) = focus.entity.map(|e| buttons.get_mut(e)) {
+            interaction.set_changed();
+        }
+    }
+    focus.entity = next;
+    focus.position = next
+        .and_then(|entity| buttons.get(entity).ok())
+        .map(|(_, transform, ..)| transform.translation().truncate());
+    let Some(entity) = next else {
+        return;
+    };
+    if !focus.locked && actions.take(Action::Confirm) {
+        if let Ok((_, _, mut interaction, _)) = buttons.get_mut(entity) {
+            *interaction = Interaction::Clicked;
+            focus.pressed = Some(entity);
+        }
+    }
+}
+
+/// 只改显示为普通颜色的按钮，选中的和还不能点的按钮保持原来的颜色
+fn highlight_focus(
+    focus: Res<Focus>,
+    button_colors: Res<ButtonColors>,
+    mut buttons: Query<&mut UiColor, With<Button>>,
+) {
+    let Some(Ok(mut color)) = focus.entity.map(|entity| buttons.get_mut(entity)) else {
+        return;
+    };
+    if color.0 == button_colors.normal.0 {
+        *color = button_colors.hovered;
+    }
+}
@@ -0,0 +1,215 @@
+//! 游戏结束画面：显示本局得分和它的组成、最高分，以及重来和返回菜单两个按钮，
+//! 关卡完成时也显示这个画面
+
+use bevy::prelude::*;
+
+use crate::common::clear_entities;
+use crate::highscore::HighScores;
+use crate::letterbox::UiRoot;
+use crate::loading::FontAssets;
+use crate::menu::ButtonColors;
+use crate::score::Score;
+use crate::waves::WaveDirector;
+use crate::GameState;
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputDelay>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(reset_input_delay)
+                    .with_system(setup_game_over),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(tick_input_delay)
+                    .with_system(click_game_over_button.after(tick_input_delay)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameOver)
+                    .with_system(clear_entities::<GameOverScreen>),
+            );
+    }
+}
+
+/// 游戏结束后需要等一会儿才响应输入，避免玩家把按钮连带点掉
+pub struct InputDelay(Timer);
+
+impl Default for InputDelay {
+    fn default() -> Self {
+        Self(Timer::from_seconds(1.0, false))
+    }
+}
+
+impl InputDelay {
+    #[must_use]
+    pub fn ready(&self) -> bool {
+        self.0.finished()
+    }
+}
+
+#[derive(Component)]
+struct GameOverScreen;
+
+#[derive(Component, Clone, Copy)]
+pub enum GameOverButton {
+    Retry,
+    MainMenu,
+}
+
+/// 还不能点击时按钮的颜色
+const DISABLED_BUTTON: Color = Color::rgb(0.08, 0.08, 0.08);
+
+fn reset_input_delay(mut delay: ResMut<InputDelay>) {
+    delay.0.reset();
+}
+
+fn setup_game_over(
+    mut commands: Commands,
+    ui_root: Res<UiRoot>,
+    font_assets: Res<FontAssets>,
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    director: Res<WaveDirector>,
+) {
+    let text = |value: String, font_size: f32, color: Color, top: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.xolonium_regular.clone(),
+                font_size,
+                color,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(top),
+                ..default()
+            },
+            ..default()
+        })
+    };
+    let points = score.points();
+    let best = high_scores.best().max(points);
+    let entity = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(GameOverScreen)
+        .with_children(|parent| {
+            let (title, size) = if director.is_complete() {
+                ("Level Complete!", 44.0)
+            } else {
+                ("Game Over", 60.0)
+            };
+            parent.spawn_bundle(text(title.to_owned(), size, Color::WHITE, 120.0));
+            parent.spawn_bundle(text(format!("Score: {points}"), 32.0, Color::GOLD, 220.0));
+            parent.spawn_bundle(text(format!("Best: {best}"), 24.0, Color::WHITE, 265.0));
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let breakdown = [
+                format!("Survived {:.0}s  +{:.0}", score.time, score.survival),
+                format!("Near misses {}  +{:.0}", score.near_misses, score.near_miss),
+                format!("Best streak {}", score.best_streak),
+                format!("Pickups {}  +{:.0}", score.pickups, score.pickup),
+            ];
+            for (i, line) in breakdown.into_iter().enumerate() {
+                #[allow(clippy::cast_precision_loss)]
+                let top = 25.0f32.mul_add(i as f32, 310.0);
+                parent.spawn_bundle(text(line, 18.0, Color::rgb(0.8, 0.8, 0.8), top));
+            }
+        })
+        .id();
+    ui_root.add(&mut commands, entity);
+    for (button, label, bottom) in [
+        (GameOverButton::Retry, "Retry", 140.0),
+        (GameOverButton::MainMenu, "Main Menu", 50.0),
+    ] {
+        let entity = commands
+            .spawn_bundle(ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(240.0), Val::Px(70.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(bottom),
+                        left: Val::Px(80.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                color: DISABLED_BUTTON.into(),
+                ..default()
+            })
+            .insert(button)
+            .insert(GameOverScreen)
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: font_assets.xolonium_regular.clone(),
+                        font_size: 40.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ));
+            })
+            .id();
+        ui_root.add(&mut commands, entity);
+    }
+}
+
+pub fn tick_input_delay(
+    time: Res<Time>,
+    button_colors: Res<ButtonColors>,
+    mut delay: ResMut<InputDelay>,
+    mut buttons: Query<&mut UiColor, With<GameOverButton>>,
+) {
+    if delay.0.tick(time.delta()).just_finished() {
+        for mut color in &mut buttons {
+            *color = button_colors.normal;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn click_game_over_button(
+    button_colors: Res<ButtonColors>,
+    delay: Res<InputDelay>,
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<
+        (&Interaction, &mut UiColor, &GameOverButton),
+        Changed<Interaction>,
+    >,
+) {
+    if !delay.ready() {
+        return;
+    }
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                let next = match button {
+                    GameOverButton::Retry => GameState::Playing,
+                    GameOverButton::MainMenu => GameState::Menu,
+                };
+                if let Err(e) = state.set(next) {
+                    debug!("ignoring game over click: {e:?}");
+                }
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        }
+    }
+}
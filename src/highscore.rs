@@ -0,0 +1,437 @@
+//! 本地排行榜，保存在用户数据目录下的 `dodge-the-creeps/highscores.ron`
+
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::{Action, ActionInput};
+use crate::common::clear_entities;
+use crate::common::rng::GameRng;
+use crate::focus::Focus;
+use crate::game_over::{tick_input_delay, InputDelay};
+use crate::letterbox::UiRoot;
+use crate::loading::FontAssets;
+use crate::player::GameOverEvent;
+use crate::replay::{finish_playback, Playback};
+use crate::score::Score;
+use crate::waves::GameMode;
+use crate::GameState;
+
+/// 排行榜保留的条数
+const MAX_ENTRIES: usize = 5;
+const MAX_NAME_LEN: usize = 12;
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(check_high_score.before(finish_playback)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(enter_name.after(tick_input_delay))
+                    .with_system(show_name_prompt.after(enter_name))
+                    .with_system(lock_focus.after(enter_name)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameOver)
+                    .with_system(submit_pending)
+                    .with_system(clear_entities::<NamePrompt>),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(show_high_scores))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Menu).with_system(clear_entities::<HighScoreTable>),
+            );
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct HighScoreEntry {
+    name: String,
+    score: u32,
+    /// unix 时间戳，单位秒，未知时为 0
+    timestamp: u64,
+    seed: u64,
+}
+
+/// 按分数从高到低排列
+#[derive(Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default)]
+    entries: Vec<HighScoreEntry>,
+    /// 上一次输入的名字，作为下一次的默认值
+    #[serde(default)]
+    last_name: String,
+}
+
+impl HighScores {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("dodge-the-creeps").join("highscores.ron"))
+    }
+
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|s| ron::from_str(&s).ok())
+            .unwrap_or(Self {
+                entries: Vec::new(),
+                last_name: String::new(),
+            })
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        let result = ron::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|s| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, s).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            error!("failed to save high scores to {}: {e}", path.display());
+        }
+    }
+
+    /// 排行榜上的最高分，没有记录时为 0
+    #[must_use]
+    pub fn best(&self) -> u32 {
+        self.entries.first().map_or(0, |e| e.score)
+    }
+
+    /// 这个分数能否进入排行榜
+    fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().is_none_or(|e| score > e.score))
+    }
+
+    /// 插入一条记录并保存到文件
+    fn submit(&mut self, entry: HighScoreEntry) {
+        self.insert(entry);
+        self.save();
+    }
+
+    /// 同分时先到的排在前面，超出 [`MAX_ENTRIES`] 的记录被挤掉
+    fn insert(&mut self, mut entry: HighScoreEntry) {
+        entry.name = String::from(entry.name.trim());
+        if entry.name.is_empty() {
+            entry.name = String::from("Player");
+        }
+        let i = self.entries.partition_point(|e| e.score >= entry.score);
+        self.last_name.clone_from(&entry.name);
+        self.entries.insert(i, entry);
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+/// 本局进了排行榜，正在输入名字
+struct PendingHighScore(HighScoreEntry);
+
+#[derive(Component)]
+struct HighScoreTable;
+
+#[derive(Component)]
+struct NamePrompt;
+
+fn now() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        0
+    }
+}
+
+/// 把 unix 时间戳格式化成 `YYYY-MM-DD`
+fn format_date(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "-".to_owned();
+    }
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = i64::try_from(timestamp / 86400).unwrap_or(0) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn check_high_score(
+    mut commands: Commands,
+    score: Res<Score>,
+    rng: Res<GameRng>,
+    high_scores: Res<HighScores>,
+    playback: Option<Res<Playback>>,
+    mode: Res<GameMode>,
+    game_over: EventReader<GameOverEvent>,
+) {
+    // 暂停后中途退出的不算
+    if game_over.is_empty() {
+        return;
+    }
+    // 回放的是以前的某一局，不再计入排行榜
+    if playback.is_some_and(|p| p.is_active()) {
+        return;
+    }
+    // 排行榜只记录无尽模式
+    if !matches!(*mode, GameMode::Endless) {
+        return;
+    }
+    let points = score.points();
+    if high_scores.qualifies(points) {
+        commands.insert_resource(PendingHighScore(HighScoreEntry {
+            name: high_scores.last_name.clone(),
+            score: points,
+            timestamp: now(),
+            seed: rng.seed(),
+        }));
+    }
+}
+
+fn enter_name(
+    mut commands: Commands,
+    mut chars: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    actions: ActionInput,
+    delay: Res<InputDelay>,
+    mut high_scores: ResMut<HighScores>,
+    pending: Option<ResMut<PendingHighScore>>,
+) {
+    // 刚死的时候还按着的键不算输入
+    let (Some(mut pending), true) = (pending, delay.ready()) else {
+        chars.clear();
+        return;
+    };
+    let name = &mut pending.0.name;
+    for c in chars.iter().map(|c| c.char).filter(|c| !c.is_control()) {
+        if name.chars().count() < MAX_NAME_LEN {
+            name.push(c);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        name.pop();
+    }
+    if actions.just_pressed(Action::Confirm) {
+        high_scores.submit(pending.0.clone());
+        commands.remove_resource::<PendingHighScore>();
+    }
+}
+
+/// 输入名字时确认键用来保存名字，不能同时按下按钮
+fn lock_focus(pending: Option<Res<PendingHighScore>>, mut focus: ResMut<Focus>) {
+    focus.locked = pending.is_some();
+}
+
+/// 不输入名字直接开始下一局时，用当前的名字保存
+fn submit_pending(
+    mut commands: Commands,
+    mut high_scores: ResMut<HighScores>,
+    pending: Option<Res<PendingHighScore>>,
+) {
+    if let Some(pending) = pending {
+        high_scores.submit(pending.0.clone());
+        commands.remove_resource::<PendingHighScore>();
+    }
+}
+
+/// 进了排行榜时在游戏结束画面上输入名字
+fn show_name_prompt(
+    mut commands: Commands,
+    ui_root: Res<UiRoot>,
+    font_assets: Res<FontAssets>,
+    pending: Option<Res<PendingHighScore>>,
+    prompt: Query<Entity, With<NamePrompt>>,
+) {
+    let Some(pending) = pending else {
+        for entity in &prompt {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+    if !prompt.is_empty() && !pending.is_changed() {
+        return;
+    }
+    for entity in &prompt {
+        commands.entity(entity).despawn_recursive();
+    }
+    let text = |value: String, font_size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.xolonium_regular.clone(),
+                font_size,
+                color,
+            },
+        )
+    };
+    let entity = commands
+        .spawn_bundle(column(260.0))
+        .insert(NamePrompt)
+        .with_children(|parent| {
+            parent.spawn_bundle(text("New high score!".to_owned(), 24.0, Color::GOLD));
+            parent.spawn_bundle(text(
+                format!("Name: {}_", pending.0.name),
+                24.0,
+                Color::WHITE,
+            ));
+            parent.spawn_bundle(text("Confirm to save".to_owned(), 16.0, Color::GRAY));
+        })
+        .id();
+    ui_root.add(&mut commands, entity);
+}
+
+fn show_high_scores(
+    mut commands: Commands,
+    ui_root: Res<UiRoot>,
+    font_assets: Res<FontAssets>,
+    high_scores: Res<HighScores>,
+    table: Query<Entity, With<HighScoreTable>>,
+) {
+    if !table.is_empty() && !high_scores.is_changed() {
+        return;
+    }
+    for entity in &table {
+        commands.entity(entity).despawn_recursive();
+    }
+    if high_scores.entries.is_empty() {
+        return;
+    }
+    let text = |value: String, font_size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.xolonium_regular.clone(),
+                font_size,
+                color,
+            },
+        )
+    };
+    let entity = commands
+        .spawn_bundle(column(150.0))
+        .insert(HighScoreTable)
+        .with_children(|parent| {
+            parent.spawn_bundle(text("High Scores".to_owned(), 24.0, Color::GOLD));
+            for (i, entry) in high_scores.entries.iter().enumerate() {
+                parent.spawn_bundle(text(
+                    format!(
+                        "{}. {}  {}  {}",
+                        i + 1,
+                        entry.name,
+                        entry.score,
+                        format_date(entry.timestamp)
+                    ),
+                    20.0,
+                    Color::WHITE,
+                ));
+            }
+        })
+        .id();
+    ui_root.add(&mut commands, entity);
+}
+
+/// 距离底部 `bottom` 像素、水平居中、从上往下排列的一列文字
+fn column(bottom: f32) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Auto),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(bottom),
+                ..default()
+            },
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u32) -> HighScoreEntry {
+        HighScoreEntry {
+            name: name.to_owned(),
+            score,
+            timestamp: 0,
+            seed: 0,
+        }
+    }
+
+    fn table(scores: &[u32]) -> HighScores {
+        let mut high_scores = HighScores {
+            entries: Vec::new(),
+            last_name: String::new(),
+        };
+        for &score in scores {
+            high_scores.insert(entry("a", score));
+        }
+        high_scores
+    }
+
+    fn scores(high_scores: &HighScores) -> Vec<u32> {
+        high_scores.entries.iter().map(|e| e.score).collect()
+    }
+
+    #[test]
+    fn qualifies_until_the_table_is_full() {
+        let high_scores = table(&[50, 40]);
+        assert!(high_scores.qualifies(1));
+        assert!(!high_scores.qualifies(0));
+    }
+
+    #[test]
+    fn full_table_needs_a_better_score_than_the_last() {
+        let high_scores = table(&[50, 40, 30, 20, 10]);
+        assert!(high_scores.qualifies(11));
+        assert!(!high_scores.qualifies(10));
+        assert!(!high_scores.qualifies(5));
+    }
+
+    #[test]
+    fn insert_keeps_scores_sorted_and_truncated() {
+        let mut high_scores = table(&[30, 10, 50, 20, 40]);
+        assert_eq!(scores(&high_scores), [50, 40, 30, 20, 10]);
+        high_scores.insert(entry("b", 35));
+        assert_eq!(scores(&high_scores), [50, 40, 35, 30, 20]);
+        assert_eq!(high_scores.best(), 50);
+    }
+
+    #[test]
+    fn insert_puts_ties_after_earlier_entries() {
+        let mut high_scores = table(&[20]);
+        high_scores.insert(entry("late", 20));
+        assert_eq!(high_scores.entries[0].name, "a");
+        assert_eq!(high_scores.entries[1].name, "late");
+    }
+
+    #[test]
+    fn insert_trims_names_and_remembers_the_last_one() {
+        let mut high_scores = table(&[]);
+        high_scores.insert(entry("  Ana  ", 5));
+        high_scores.insert(entry("   ", 3));
+        assert_eq!(high_scores.entries[0].name, "Ana");
+        assert_eq!(high_scores.entries[1].name, "Player");
+        assert_eq!(high_scores.last_name, "Player");
+    }
+}
@@ -0,0 +1,357 @@
+//! 游戏中的界面：最上面一排是最高分和用时、得分、生命，下面一排是正在生效的道具，
+//! 再往下是一行提示（"Get Ready"、"Wave 3"），淡入之后停留一会儿再淡出
+//!
+//! 全部用 flexbox 排列，位置随游戏画面的大小变化
+
+use bevy::prelude::*;
+
+use crate::common::GameState;
+use crate::highscore::HighScores;
+use crate::letterbox::UiRoot;
+use crate::lives::{LifeLostEvent, Lives};
+use crate::loading::FontAssets;
+use crate::pickup::{PowerUp, PowerUps};
+use crate::score::Score;
+use crate::waves::WaveStartEvent;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HudMessage>()
+            .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup_hud))
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(get_ready))
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(clear_message))
+            .add_system(show_score)
+            .add_system(show_best)
+            .add_system(show_time)
+            .add_system(show_lives)
+            .add_system(show_power_ups)
+            .add_system(wave_message)
+            .add_system(lives_message)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(show_message.after(wave_message).after(lives_message)),
+            );
+    }
+}
+
+/// 在提示行显示一条消息，替换掉正在显示的
+pub struct HudMessage(pub String);
+
+/// 提示淡入的秒数
+const MESSAGE_FADE_IN: f32 = 0.2;
+/// 完全显示的秒数
+const MESSAGE_HOLD: f32 = 1.2;
+/// 淡出的秒数
+const MESSAGE_FADE_OUT: f32 = 0.6;
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct BestText;
+
+#[derive(Component)]
+struct TimeText;
+
+/// 只有一条命时不显示
+#[derive(Component)]
+struct LivesText;
+
+/// 一种道具的图标和剩余秒数，没有生效时隐藏
+#[derive(Component)]
+struct PowerUpStatus(PowerUp);
+
+#[derive(Component)]
+struct PowerUpTimer(PowerUp);
+
+/// `age` 是这条消息显示以来的秒数
+#[derive(Component)]
+struct MessageText {
+    age: f32,
+}
+
+fn text(font: &Handle<Font>, font_size: f32, color: Color) -> TextBundle {
+    TextBundle::from_section(
+        "",
+        TextStyle {
+            font: font.clone(),
+            font_size,
+            color,
+        },
+    )
+}
+
+/// 宽度按比例分的一列，`align` 决定这一列的内容靠左、居中还是靠右
+fn column(width: f32, align: AlignItems) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(width), Val::Auto),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: align,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }
+}
+
+fn row(justify: JustifyContent) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Auto),
+            justify_content: justify,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }
+}
+
+/// 占据剩下空间的 `grow` 份
+fn spacer(grow: f32) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_grow: grow,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }
+}
+
+fn setup_hud(mut commands: Commands, ui_root: Res<UiRoot>, font_assets: Res<FontAssets>) {
+    let font = &font_assets.xolonium_regular;
+    let entity = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: UiRect::all(Val::Px(10.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(row(JustifyContent::SpaceBetween))
+                .with_children(|parent| spawn_top_bar(parent, font));
+            parent
+                .spawn_bundle(row(JustifyContent::FlexStart))
+                .with_children(|parent| spawn_power_up_icons(parent, font));
+            // 提示行在剩下空间的上三分之一居中
+            parent.spawn_bundle(spacer(1.0)).with_children(|parent| {
+                parent
+                    .spawn_bundle(text(font, 36.0, Color::NONE))
+                    .insert(MessageText { age: f32::INFINITY });
+            });
+            parent.spawn_bundle(spacer(2.0));
+        })
+        .id();
+    ui_root.add(&mut commands, entity);
+}
+
+/// 左边是最高分和用时，中间是得分，右边是生命
+fn spawn_top_bar(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn_bundle(column(30.0, AlignItems::FlexStart))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(text(font, 18.0, Color::WHITE))
+                .insert(BestText);
+            parent
+                .spawn_bundle(text(font, 18.0, Color::WHITE))
+                .insert(TimeText);
+        });
+    parent
+        .spawn_bundle(column(40.0, AlignItems::Center))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(text(font, 60.0, Color::GOLD))
+                .insert(ScoreText);
+        });
+    parent
+        .spawn_bundle(column(30.0, AlignItems::FlexEnd))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(text(font, 24.0, Color::WHITE))
+                .insert(LivesText);
+        });
+}
+
+/// 每种道具一个色块加上剩余的秒数
+fn spawn_power_up_icons(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    for kind in PowerUp::ALL {
+        parent
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    align_items: AlignItems::Center,
+                    margin: UiRect {
+                        right: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                color: Color::NONE.into(),
+                ..default()
+            })
+            .insert(PowerUpStatus(kind))
+            .with_children(|parent| {
+                parent.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(16.0), Val::Px(16.0)),
+                        margin: UiRect {
+                            right: Val::Px(6.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    color: kind.color().into(),
+                    ..default()
+                });
+                parent
+                    .spawn_bundle(text(font, 18.0, kind.color()))
+                    .insert(PowerUpTimer(kind));
+            });
+    }
+}
+
+fn show_score(score: Res<Score>, mut query: Query<&mut Text, With<ScoreText>>) {
+    if !score.is_changed() {
+        return;
+    }
+    for mut text in &mut query {
+        text.sections[0].value = score.points().to_string();
+    }
+}
+
+/// 排行榜变化时更新，刚创建的文字也要填上，否则要等到下一次排行榜变化
+fn show_best(
+    high_scores: Res<HighScores>,
+    mut query: Query<(&mut Text, ChangeTrackers<BestText>)>,
+) {
+    for (mut text, tracker) in &mut query {
+        if high_scores.is_changed() || tracker.is_added() {
+            text.sections[0].value = format!("Best {}", high_scores.best());
+        }
+    }
+}
+
+fn show_time(score: Res<Score>, mut query: Query<&mut Text, With<TimeText>>) {
+    if !score.is_changed() {
+        return;
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let seconds = score.time as u32;
+    for mut text in &mut query {
+        text.sections[0].value = format!("{}:{:02}", seconds / 60, seconds % 60);
+    }
+}
+
+fn show_lives(lives: Res<Lives>, mut query: Query<&mut Text, With<LivesText>>) {
+    if !lives.is_changed() {
+        return;
+    }
+    for mut text in &mut query {
+        text.sections[0].value = if lives.max() > 1 {
+            format!("Lives {}", lives.remaining())
+        } else {
+            String::new()
+        };
+    }
+}
+
+fn show_power_ups(
+    power_ups: Res<PowerUps>,
+    mut statuses: Query<(&mut Style, &PowerUpStatus)>,
+    mut timers: Query<(&mut Text, &PowerUpTimer)>,
+) {
+    if !power_ups.is_changed() {
+        return;
+    }
+    for (mut style, status) in &mut statuses {
+        let display = if power_ups.is_active(status.0) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    for (mut text, timer) in &mut timers {
+        text.sections[0].value = format!("{:.1}", power_ups.remaining(timer.0));
+    }
+}
+
+fn get_ready(mut messages: EventWriter<HudMessage>) {
+    messages.send(HudMessage("Get Ready".to_owned()));
+}
+
+fn wave_message(mut events: EventReader<WaveStartEvent>, mut messages: EventWriter<HudMessage>) {
+    for event in events.iter() {
+        messages.send(HudMessage(format!("Wave {}", event.number)));
+    }
+}
+
+fn lives_message(
+    lives: Res<Lives>,
+    mut events: EventReader<LifeLostEvent>,
+    mut messages: EventWriter<HudMessage>,
+) {
+    if events.iter().last().is_some() {
+        let message = match lives.remaining() {
+            1 => "Last life!".to_owned(),
+            n => format!("{n} lives left"),
+        };
+        messages.send(HudMessage(message));
+    }
+}
+
+/// 显示最新的一条消息，按显示的时间淡入淡出，暂停时停住
+fn show_message(
+    time: Res<Time>,
+    mut messages: EventReader<HudMessage>,
+    mut query: Query<(&mut Text, &mut MessageText)>,
+) {
+    let latest = messages.iter().last();
+    for (mut text, mut message) in &mut query {
+        if let Some(latest) = latest {
+            text.sections[0].value.clone_from(&latest.0);
+            message.age = 0.0;
+        } else if message.age.is_infinite() {
+            continue;
+        } else {
+            message.age += time.delta_seconds();
+        }
+        let age = message.age;
+        let alpha = if age < MESSAGE_FADE_IN {
+            age / MESSAGE_FADE_IN
+        } else if age < MESSAGE_FADE_IN + MESSAGE_HOLD {
+            1.0
+        } else {
+            1.0 - (age - MESSAGE_FADE_IN - MESSAGE_HOLD) / MESSAGE_FADE_OUT
+        };
+        if alpha <= 0.0 {
+            text.sections[0].value.clear();
+            message.age = f32::INFINITY;
+        }
+        let style = &mut text.sections[0].style;
+        style.color = Color::rgba(1.0, 1.0, 1.0, alpha.clamp(0.0, 1.0));
+    }
+}
+
+/// 一局结束时清掉还没淡出的消息
+fn clear_message(mut query: Query<(&mut Text, &mut MessageText)>) {
+    for (mut text, mut message) in &mut query {
+        text.sections[0].value.clear();
+        message.age = f32::INFINITY;
+    }
+}
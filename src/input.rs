@@ -0,0 +1,123 @@
+//! 把键盘、手柄、鼠标和触摸统一成玩家的移动方向 [`PlayerInput`]
+//!
+//! 方向的长度不超过 1，手柄摇杆推得越浅移动越慢
+
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::bindings::{Action, ActionInput};
+use crate::player::{Player, PlayerInput};
+
+/// 摇杆的径向死区，小于这个幅度视为没有推动
+const STICK_DEADZONE: f32 = 0.2;
+/// 离指针这么近时停下，避免在指针附近来回抖动
+const POINTER_STOP_DISTANCE: f32 = 10.0;
+
+#[derive(SystemParam)]
+pub struct GamepadInput<'w, 's> {
+    gamepads: Res<'w, Gamepads>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl GamepadInput<'_, '_> {
+    /// 所有手柄的左摇杆
+    fn movement(&self) -> Vec2 {
+        let mut movement = Vec2::ZERO;
+        for &gamepad in self.gamepads.iter() {
+            let axis = |axis_type| {
+                self.axes
+                    .get(GamepadAxis::new(gamepad, axis_type))
+                    .unwrap_or_default()
+            };
+            let stick = Vec2::new(
+                axis(GamepadAxisType::LeftStickX),
+                axis(GamepadAxisType::LeftStickY),
+            );
+            // 去掉死区之后把剩下的幅度重新映射到 0..=1
+            let length = stick.length();
+            if length > STICK_DEADZONE {
+                let scaled = ((length - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0);
+                movement += stick / length * scaled;
+            }
+        }
+        movement
+    }
+}
+
+#[derive(SystemParam)]
+pub struct PointerInput<'w, 's> {
+    mouse_buttons: Res<'w, Input<MouseButton>>,
+    touches: Res<'w, Touches>,
+    windows: Option<Res<'w, Windows>>,
+    player: Query<'w, 's, &'static Transform, With<Player>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<Camera2d>>,
+}
+
+impl PointerInput<'_, '_> {
+    /// 按住鼠标左键或者触摸屏幕时，朝指针所在的位置移动
+    fn movement(&self) -> Vec2 {
+        let Some(window) = self.windows.as_ref().and_then(|w| w.get_primary()) else {
+            return Vec2::ZERO;
+        };
+        let half_size = Vec2::new(window.width(), window.height()) / 2.0;
+        // 鼠标坐标的原点在左下角，网页和桌面上触摸坐标的原点在左上角
+        let offset = if let Some(position) = self.touches.first_pressed_position() {
+            Vec2::new(position.x - half_size.x, half_size.y - position.y)
+        } else if self.mouse_buttons.pressed(MouseButton::Left) {
+            let Some(position) = window.cursor_position() else {
+                return Vec2::ZERO;
+            };
+            position - half_size
+        } else {
+            return Vec2::ZERO;
+        };
+        // 相机按窗口比例缩放并留出黑边，用它的投影换算成世界坐标
+        let Ok((camera, transform)) = self.cameras.get_single() else {
+            return Vec2::ZERO;
+        };
+        let ndc_to_world = transform.compute_matrix() * camera.projection_matrix().inverse();
+        let target = ndc_to_world
+            .project_point3((offset / half_size).extend(-1.0))
+            .truncate();
+        let Ok(player) = self.player.get_single() else {
+            return Vec2::ZERO;
+        };
+        let offset = target - player.translation.truncate();
+        if offset.length() > POINTER_STOP_DISTANCE {
+            offset.normalize()
+        } else {
+            Vec2::ZERO
+        }
+    }
+}
+
+/// 绑定到上下左右的按键合成一个方向，相反的两个键同时按下时互相抵消
+fn digital_movement(actions: &ActionInput) -> Vec2 {
+    let axis = |negative, positive| match (actions.pressed(negative), actions.pressed(positive)) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+    Vec2::new(
+        axis(Action::MoveLeft, Action::MoveRight),
+        axis(Action::MoveDown, Action::MoveUp),
+    )
+}
+
+/// 键盘和手柄优先，都没有操作时才跟随鼠标或触摸
+pub fn read_player_input(
+    actions: ActionInput,
+    gamepad: GamepadInput,
+    pointer: PointerInput,
+    mut input: ResMut<PlayerInput>,
+) {
+    let mut movement = digital_movement(&actions) + gamepad.movement();
+    if movement == Vec2::ZERO {
+        movement = pointer.movement();
+    }
+    input.movement = movement.clamp_length_max(1.0);
+}
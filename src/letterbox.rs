@@ -0,0 +1,121 @@
+//! 不管窗口多大，相机始终完整显示 [`GameConfig::window`] 大小的游戏画面，等比缩放，
+//! 窗口比例不同时多出来的部分用黑边盖住
+//!
+//! 界面都挂在 [`UiRoot`] 下面，它和游戏画面对齐，界面的位置都相对游戏画面计算
+
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+
+use crate::config::GameConfig;
+
+pub struct LetterboxPlugin;
+
+impl Plugin for LetterboxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_letterbox)
+            .add_system(update_letterbox)
+            .add_system(update_ui_root);
+    }
+}
+
+/// 和游戏画面对齐的界面根节点
+pub struct UiRoot(Entity);
+
+impl UiRoot {
+    /// 把 `entity` 挂到根节点下面
+    pub fn add(&self, commands: &mut Commands, entity: Entity) {
+        commands.entity(self.0).add_child(entity);
+    }
+}
+
+/// 黑边的边长，足够盖住任何窗口
+const BAR_SIZE: f32 = 100_000.0;
+/// 在所有精灵上面
+const BAR_Z: f32 = 900.0;
+
+/// 黑边在游戏画面的哪一侧
+#[derive(Component, Clone, Copy)]
+struct Bar(Vec2);
+
+/// 等比缩放，保证宽高都能完整显示
+#[must_use]
+pub const fn camera_scaling(config: &GameConfig) -> ScalingMode {
+    ScalingMode::Auto {
+        min_width: config.window.x,
+        min_height: config.window.y,
+    }
+}
+
+fn setup_letterbox(mut commands: Commands) {
+    for side in [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y] {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::BLACK,
+                    custom_size: Some(Vec2::splat(BAR_SIZE)),
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Bar(side));
+    }
+    let root = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .id();
+    commands.insert_resource(UiRoot(root));
+}
+
+/// 配置里的画面大小变化时，调整相机和黑边
+fn update_letterbox(
+    config: Res<GameConfig>,
+    mut cameras: Query<&mut OrthographicProjection, With<Camera2d>>,
+    mut bars: Query<(&mut Transform, &Bar)>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    for mut projection in &mut cameras {
+        projection.scaling_mode = camera_scaling(&config);
+    }
+    let half_size = config.window / 2.0;
+    for (mut transform, bar) in &mut bars {
+        let offset = bar.0 * (half_size + BAR_SIZE / 2.0);
+        transform.translation = offset.extend(BAR_Z);
+    }
+}
+
+/// 根节点的位置和大小，单位是窗口的逻辑像素
+fn update_ui_root(
+    config: Res<GameConfig>,
+    windows: Res<Windows>,
+    root: Option<Res<UiRoot>>,
+    mut styles: Query<&mut Style>,
+) {
+    let (Some(root), Some(window)) = (root, windows.get_primary()) else {
+        return;
+    };
+    let Ok(mut style) = styles.get_mut(root.0) else {
+        return;
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+    let scale = (window_size / config.window).min_element();
+    let size = config.window * scale;
+    let offset = (window_size - size) / 2.0;
+    let next_size = Size::new(Val::Px(size.x), Val::Px(size.y));
+    let next_position = UiRect {
+        left: Val::Px(offset.x),
+        top: Val::Px(offset.y),
+        ..default()
+    };
+    if style.size != next_size || style.position != next_position {
+        style.size = next_size;
+        style.position = next_position;
+    }
+}
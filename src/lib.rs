@@ -1,53 +1,105 @@
+mod arena;
 mod audio;
+mod bindings;
+mod collision;
 mod common;
+mod config;
+mod controls;
+mod difficulty;
 mod enemy;
+mod focus;
+mod game_over;
+mod highscore;
+mod hud;
+mod input;
+mod letterbox;
+mod lives;
 mod loading;
 mod menu;
+mod music;
+mod pause;
+mod pickup;
 mod player;
-mod ui;
+mod replay;
+mod score;
+mod settings;
+mod settings_menu;
+mod sfx;
+mod waves;
 
 use bevy::app::App;
 #[cfg(debug_assertions)]
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::hierarchy::HierarchyPlugin;
+use bevy::input::InputPlugin;
 use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
 use bevy_rapier2d::prelude::*;
 use common::animation::AnimationPlugin;
-use common::path::Path;
-use common::GameState;
-use enemy::EnemyPlugin;
-use ui::UiPlugin;
+use common::rng::RngPlugin;
+pub use common::rng::RngSeed;
+pub use common::tick::{FixedTick, TickAppExt, TickStage};
+use common::tick::{GameTick, TickPlugin};
+pub use common::GameState;
+pub use enemy::Enemy;
+use enemy::{EnemyPlugin, EnemySpritePlugin};
 
+pub use crate::arena::Arena;
+use crate::arena::ArenaPlugin;
 use crate::audio::InternalAudioPlugin;
+use crate::bindings::Bindings;
+use crate::collision::CollisionPlugin;
+use crate::config::ConfigPlugin;
+pub use crate::config::GameConfig;
+use crate::controls::ControlsPlugin;
+pub use crate::difficulty::Difficulty;
+use crate::difficulty::DifficultyPlugin;
+use crate::focus::FocusPlugin;
+use crate::game_over::GameOverPlugin;
+use crate::highscore::HighScorePlugin;
+use crate::hud::HudPlugin;
+use crate::letterbox::LetterboxPlugin;
+use crate::lives::LivesPlugin;
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
-use crate::player::PlayerPlugin;
+use crate::pause::PausePlugin;
+use crate::pickup::{PickupPlugin, PickupSpritePlugin};
+use crate::player::{PlayerPlugin, PlayerSpritePlugin};
+use crate::replay::ReplayPlugin;
+pub use crate::replay::{Playback, Recorder, Recording};
+pub use crate::score::Score;
+use crate::score::{ScorePlugin, ScorePopupPlugin};
+use crate::settings::SettingsPlugin;
+use crate::settings_menu::SettingsMenuPlugin;
+pub use crate::waves::{GameMode, Level, WaveDirector};
+use crate::waves::{LevelPlugin, WavePlugin};
 
-#[derive(Deref)]
-pub struct ScreenPath(Path<4>);
-impl Default for ScreenPath {
-    fn default() -> Self {
-        Self(Path([
-            Vec2::new(-200., 360.),
-            Vec2::new(200., 360.),
-            Vec2::new(200., -360.),
-            Vec2::new(-200., -360.),
-        ]))
-    }
-}
+/// 完整的游戏：资源加载、菜单、贴图、音效以及游戏逻辑
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state(GameState::Loading)
-            .add_plugin(UiPlugin)
+            .add_plugin(SimulationPlugin)
+            .add_plugin(ConfigPlugin)
+            .add_plugin(LevelPlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(SettingsMenuPlugin)
+            .add_plugin(LetterboxPlugin)
+            .add_plugin(HudPlugin)
             .add_plugin(LoadingPlugin)
             .add_plugin(MenuPlugin)
+            .add_plugin(FocusPlugin)
+            .add_plugin(GameOverPlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(HighScorePlugin)
             .add_plugin(InternalAudioPlugin)
-            .add_plugin(PlayerPlugin)
-            .add_plugin(EnemyPlugin)
+            .add_plugin(PlayerSpritePlugin)
+            .add_plugin(EnemySpritePlugin)
+            .add_plugin(PickupSpritePlugin)
+            .add_plugin(ScorePopupPlugin)
             .add_plugin(AnimationPlugin)
-            .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
-            .init_resource::<ScreenPath>()
             .add_startup_system(setup);
 
         #[cfg(debug_assertions)]
@@ -59,7 +111,103 @@ impl Plugin for GamePlugin {
     }
 }
 
-pub fn setup(mut commands: Commands, mut rapier_configuration: ResMut<RapierConfiguration>) {
-    commands.spawn_bundle(Camera2dBundle::default());
+/// 只包含游戏逻辑（玩家、敌人、物理），不依赖资源、窗口和 GPU
+///
+/// `GameState` 由调用方添加，见 [`GamePlugin`] 和 [`headless_app`]
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameConfig>()
+            .init_resource::<Bindings>()
+            .add_plugin(TickPlugin)
+            .add_plugin(ArenaPlugin)
+            .add_plugin(CollisionPlugin)
+            .add_plugin(RngPlugin)
+            .add_plugin(DifficultyPlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(LivesPlugin)
+            .add_plugin(EnemyPlugin)
+            .add_plugin(WavePlugin)
+            .add_plugin(PickupPlugin)
+            .add_plugin(ScorePlugin)
+            .add_plugin(ReplayPlugin)
+            .add_plugin(
+                RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0)
+                    .with_default_system_setup(false),
+            )
+            .add_startup_system(setup_physics)
+            .add_tick_system(TickStage::PreUpdate, sync_physics_timestep)
+            .add_stage_before(
+                CoreStage::Last,
+                PhysicsStages::DetectDespawn,
+                SystemStage::parallel()
+                    .with_system_set(physics_systems(PhysicsStages::DetectDespawn)),
+            );
+        // 物理引擎和游戏逻辑一起按固定步长运行
+        app.schedule.stage(GameTick, |schedule: &mut Schedule| {
+            schedule
+                .add_stage_after(
+                    TickStage::Update,
+                    PhysicsStages::SyncBackend,
+                    SystemStage::parallel()
+                        .with_system_set(physics_systems(PhysicsStages::SyncBackend)),
+                )
+                .add_stage_after(
+                    PhysicsStages::SyncBackend,
+                    PhysicsStages::StepSimulation,
+                    SystemStage::parallel()
+                        .with_system_set(physics_systems(PhysicsStages::StepSimulation)),
+                )
+                .add_stage_after(
+                    PhysicsStages::StepSimulation,
+                    PhysicsStages::Writeback,
+                    SystemStage::parallel()
+                        .with_system_set(physics_systems(PhysicsStages::Writeback)),
+                )
+        });
+    }
+}
+
+fn physics_systems(stage: PhysicsStages) -> SystemSet {
+    RapierPhysicsPlugin::<NoUserData>::get_systems(stage)
+}
+
+/// 无窗口、无渲染、无音效地运行 [`GameState::Playing`]
+///
+/// 用于在没有显示器的机器上跑压力测试和集成测试，通过 [`App::update`] 或 [`App::run`] 驱动
+#[must_use]
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(InputPlugin)
+        .add_state(GameState::Playing)
+        .add_plugin(SimulationPlugin);
+    app
+}
+
+/// 相机始终完整显示 [`GameConfig::window`] 大小的画面，见 [`letterbox`]
+pub fn setup(mut commands: Commands, config: Res<GameConfig>) {
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = letterbox::camera_scaling(&config);
+    commands.spawn_bundle(camera);
+}
+
+fn setup_physics(mut rapier_configuration: ResMut<RapierConfiguration>) {
     rapier_configuration.gravity = Vec2::ZERO;
 }
+
+fn sync_physics_timestep(
+    tick: Res<FixedTick>,
+    mut rapier_configuration: ResMut<RapierConfiguration>,
+) {
+    let timestep_mode = TimestepMode::Fixed {
+        dt: tick.delta_seconds(),
+        substeps: 1,
+    };
+    if rapier_configuration.timestep_mode != timestep_mode {
+        rapier_configuration.timestep_mode = timestep_mode;
+    }
+}
@@ -0,0 +1,139 @@
+//! 生命：每种难度可以配置几条命，被撞到时少一条命，并短暂无敌、被撞开一段距离，
+//! 最后一条命用完才结束游戏
+//!
+//! 一条命时和原来一样，一碰就结束
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::collision::CollisionRole;
+use crate::common::tick::{FixedTick, TickAppExt, TickStage};
+use crate::config::GameConfig;
+use crate::difficulty::Difficulty;
+use crate::replay::start_playback;
+use crate::GameState;
+
+pub struct LivesPlugin;
+
+impl Plugin for LivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lives>()
+            .add_event::<LifeLostEvent>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_lives.after(start_playback)),
+            )
+            .add_tick_system(TickStage::PreUpdate, tick_invulnerability);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LivesConfig {
+    /// 每种难度的生命数
+    pub easy: u32,
+    pub normal: u32,
+    pub hard: u32,
+    /// 少一条命之后无敌的秒数
+    pub invulnerability: f32,
+    /// 被撞开的初速度，单位像素每秒
+    pub knockback: f32,
+}
+
+impl Default for LivesConfig {
+    fn default() -> Self {
+        Self {
+            easy: 3,
+            normal: 1,
+            hard: 1,
+            invulnerability: 1.5,
+            knockback: 600.0,
+        }
+    }
+}
+
+impl LivesConfig {
+    #[must_use]
+    pub const fn get(&self, difficulty: Difficulty) -> u32 {
+        match difficulty {
+            Difficulty::Easy => self.easy,
+            Difficulty::Normal => self.normal,
+            Difficulty::Hard => self.hard,
+        }
+    }
+}
+
+/// 这一局剩下的生命
+#[derive(Default)]
+pub struct Lives {
+    remaining: u32,
+    max: u32,
+}
+
+impl Lives {
+    #[must_use]
+    pub const fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    #[must_use]
+    pub const fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// 少一条命，返回 true 表示还有剩下的
+    pub const fn lose(&mut self) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.remaining > 0
+    }
+}
+
+/// 玩家少了一条命，`position` 是撞上来的敌人的位置
+pub struct LifeLostEvent {
+    pub position: Vec2,
+}
+
+/// 无敌状态，`remaining` 是剩余的秒数
+#[derive(Component)]
+pub struct Invulnerable {
+    remaining: f32,
+}
+
+impl Invulnerable {
+    #[must_use]
+    pub const fn remaining(&self) -> f32 {
+        self.remaining
+    }
+}
+
+/// 让玩家无敌 `seconds` 秒，期间不和敌人碰撞
+pub fn make_invulnerable(commands: &mut Commands, player: Entity, seconds: f32) {
+    commands
+        .entity(player)
+        .insert(Invulnerable { remaining: seconds })
+        .insert(CollisionRole::Player.groups_ignoring(CollisionRole::Enemy));
+}
+
+fn reset_lives(config: Res<GameConfig>, difficulty: Res<Difficulty>, mut lives: ResMut<Lives>) {
+    let max = config.lives.get(*difficulty).max(1);
+    *lives = Lives {
+        remaining: max,
+        max,
+    };
+}
+
+fn tick_invulnerability(
+    mut commands: Commands,
+    tick: Res<FixedTick>,
+    mut query: Query<(Entity, &mut Invulnerable)>,
+) {
+    for (entity, mut invulnerable) in &mut query {
+        invulnerable.remaining -= tick.delta_seconds();
+        if invulnerable.remaining <= 0.0 {
+            commands
+                .entity(entity)
+                .remove::<Invulnerable>()
+                .insert(CollisionRole::Player.groups());
+        }
+    }
+}
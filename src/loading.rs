@@ -2,6 +2,9 @@ use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
 
+use crate::config::GameConfig;
+use crate::enemy::EnemyKind;
+use crate::waves::Level;
 use crate::GameState;
 
 fn general_texture_atlas_handle(
@@ -23,6 +26,8 @@ impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
         app.add_loading_state(
             LoadingState::new(GameState::Loading)
+                .with_collection::<ConfigAssets>()
+                .with_collection::<LevelAssets>()
                 .with_collection::<FontAssets>()
                 .with_collection::<AudioAssets>()
                 .with_collection::<TextureAssets>()
@@ -41,6 +46,22 @@ impl Plugin for LoadingPlugin {
 // the following asset collections will be loaded during the State `GameState::Loading`
 // when done loading, they will be inserted as resources (see https://github.com/NiklasEi/bevy_asset_loader)
 
+#[derive(AssetCollection)]
+pub struct ConfigAssets {
+    #[asset(path = "game.config.ron")]
+    pub game: Handle<GameConfig>,
+}
+
+/// 菜单里可以选择的关卡，按这个顺序排列
+#[derive(AssetCollection)]
+pub struct LevelAssets {
+    #[asset(
+        paths("levels/first_steps.level.ron", "levels/crossfire.level.ron"),
+        collection(typed)
+    )]
+    pub levels: Vec<Handle<Level>>,
+}
+
 #[derive(AssetCollection)]
 pub struct FontAssets {
     #[asset(path = "fonts/Xolonium-Regular.ttf")]
@@ -49,8 +70,10 @@ pub struct FontAssets {
 
 #[derive(AssetCollection)]
 pub struct AudioAssets {
-    #[asset(path = "audio/gameover.wav")]
-    pub gameover: Handle<AudioSource>,
+    #[asset(path = "audio/menu.wav")]
+    pub menu_music: Handle<AudioSource>,
+    #[asset(path = "audio/game.wav")]
+    pub game_music: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection)]
@@ -93,14 +116,11 @@ pub struct EnemyTextureAtlas {
     pub walk: Handle<TextureAtlas>,
 }
 impl EnemyTextureAtlas {
-    pub fn random(&self) -> Handle<TextureAtlas> {
-        let i = rand::random::<u8>() % 3;
-        if i == 0 {
-            self.fly.clone()
-        } else if i == 1 {
-            self.swim.clone()
-        } else {
-            self.walk.clone()
+    pub fn get(&self, kind: EnemyKind) -> Handle<TextureAtlas> {
+        match kind {
+            EnemyKind::Fly => self.fly.clone(),
+            EnemyKind::Swim => self.swim.clone(),
+            EnemyKind::Walk => self.walk.clone(),
         }
     }
 }
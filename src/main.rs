@@ -3,28 +3,45 @@
 
 use std::io::Cursor;
 
+#[cfg(feature = "dev")]
+use bevy::asset::AssetServerSettings;
 use bevy::prelude::{App, ClearColor, Color, Msaa, NonSend, WindowDescriptor};
 use bevy::window::WindowId;
 use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
-use dodge_the_creeps_bevy::GamePlugin;
+use dodge_the_creeps_bevy::{FixedTick, GameConfig, GamePlugin, Playback, Recorder, RngSeed};
 use winit::window::Icon;
 
 fn main() {
-    App::new()
-        .insert_resource(Msaa { samples: 1 })
+    // 配置文件加载完之前先用默认的窗口大小
+    let window = GameConfig::default().window;
+    let mut app = App::new();
+    app.insert_resource(Msaa { samples: 1 })
         .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
         .insert_resource(WindowDescriptor {
-            width: 400.,
-            height: 720.,
+            width: window.x,
+            height: window.y,
             title: "Dodge the Creeps!".to_owned(),
             canvas: Some("#bevy".to_owned()),
             ..Default::default()
         })
-        .add_plugins(DefaultPlugins)
+        .insert_resource(RngSeed::from_args_or_env())
+        .insert_resource(FixedTick::from_args_or_env());
+    #[cfg(feature = "dev")]
+    app.insert_resource(AssetServerSettings {
+        watch_for_changes: true,
+        ..Default::default()
+    });
+    app.add_plugins(DefaultPlugins)
         .add_plugin(GamePlugin)
-        .add_startup_system(set_window_icon)
-        .run();
+        .add_startup_system(set_window_icon);
+    if let Some(recorder) = Recorder::from_args() {
+        app.insert_resource(recorder);
+    }
+    if let Some(playback) = Playback::from_args() {
+        app.insert_resource(playback);
+    }
+    app.run();
 }
 
 // Sets the icon on windows and X11
@@ -1,7 +1,10 @@
 use bevy::prelude::*;
 
 use crate::common::clear_entities;
-use crate::loading::FontAssets;
+use crate::difficulty::Difficulty;
+use crate::letterbox::UiRoot;
+use crate::loading::{FontAssets, LevelAssets};
+use crate::waves::{Level, SelectedLevel};
 use crate::GameState;
 
 pub struct MenuPlugin;
@@ -10,16 +13,23 @@ impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ButtonColors>()
             .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(setup_menu))
-            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(click_play_button))
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu)
+                    .with_system(click_play_button)
+                    .with_system(click_screen_button)
+                    .with_system(click_difficulty_button)
+                    .with_system(click_level_button),
+            )
             .add_system_set(
                 SystemSet::on_exit(GameState::Menu).with_system(clear_entities::<Button>),
             );
     }
 }
 
-struct ButtonColors {
-    normal: UiColor,
-    hovered: UiColor,
+pub struct ButtonColors {
+    pub normal: UiColor,
+    pub hovered: UiColor,
+    pub selected: UiColor,
 }
 
 impl Default for ButtonColors {
@@ -27,16 +37,161 @@ impl Default for ButtonColors {
         ButtonColors {
             normal: Color::rgb(0.15, 0.15, 0.15).into(),
             hovered: Color::rgb(0.25, 0.25, 0.25).into(),
+            selected: Color::rgb(0.45, 0.35, 0.1).into(),
         }
     }
 }
 
+#[derive(Component)]
+struct PlayButton;
+
+/// 进入另一个界面的按钮
+#[derive(Component)]
+struct ScreenButton(GameState);
+
+#[derive(Component)]
+struct DifficultyButton(Difficulty);
+
+/// 在无尽模式和各个关卡之间切换
+#[derive(Component)]
+struct LevelButton;
+
+#[derive(Component)]
+struct LevelButtonText;
+
+fn spawn_difficulty_buttons(
+    commands: &mut Commands,
+    ui_root: &UiRoot,
+    font_assets: &FontAssets,
+    button_colors: &ButtonColors,
+) {
+    for (difficulty, left) in Difficulty::ALL.into_iter().zip([20.0, 145.0, 270.0]) {
+        let entity = commands
+            .spawn_bundle(ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(110.0), Val::Px(44.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(80.0),
+                        left: Val::Px(left),
+                        ..default()
+                    },
+                    ..default()
+                },
+                color: button_colors.normal,
+                ..default()
+            })
+            .insert(DifficultyButton(difficulty))
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle::from_section(
+                    difficulty.name(),
+                    TextStyle {
+                        font: font_assets.xolonium_regular.clone(),
+                        font_size: 22.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ));
+            })
+            .id();
+        ui_root.add(commands, entity);
+    }
+}
+
+fn spawn_screen_buttons(
+    commands: &mut Commands,
+    ui_root: &UiRoot,
+    font_assets: &FontAssets,
+    button_colors: &ButtonColors,
+) {
+    for (state, label, left) in [
+        (GameState::Controls, "Controls", 20.0),
+        (GameState::Settings, "Settings", 205.0),
+    ] {
+        let entity = commands
+            .spawn_bundle(ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(175.0), Val::Px(40.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(132.0),
+                        left: Val::Px(left),
+                        ..default()
+                    },
+                    ..default()
+                },
+                color: button_colors.normal,
+                ..default()
+            })
+            .insert(ScreenButton(state))
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: font_assets.xolonium_regular.clone(),
+                        font_size: 22.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ));
+            })
+            .id();
+        ui_root.add(commands, entity);
+    }
+}
+
+fn spawn_level_button(
+    commands: &mut Commands,
+    ui_root: &UiRoot,
+    font_assets: &FontAssets,
+    button_colors: &ButtonColors,
+) {
+    let entity = commands
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(360.0), Val::Px(40.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(184.0),
+                    left: Val::Px(20.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: button_colors.normal,
+            ..default()
+        })
+        .insert(LevelButton)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font_assets.xolonium_regular.clone(),
+                        font_size: 22.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ))
+                .insert(LevelButtonText);
+        })
+        .id();
+    ui_root.add(commands, entity);
+}
+
 fn setup_menu(
     mut commands: Commands,
+    ui_root: Res<UiRoot>,
     font_assets: Res<FontAssets>,
     button_colors: Res<ButtonColors>,
 ) {
-    commands
+    spawn_difficulty_buttons(&mut commands, &ui_root, &font_assets, &button_colors);
+    spawn_screen_buttons(&mut commands, &ui_root, &font_assets, &button_colors);
+    spawn_level_button(&mut commands, &ui_root, &font_assets, &button_colors);
+    let entity = commands
         .spawn_bundle(ButtonBundle {
             style: Style {
                 size: Size::new(Val::Px(200.0), Val::Px(80.0)),
@@ -54,6 +209,7 @@ fn setup_menu(
             color: button_colors.normal,
             ..Default::default()
         })
+        .insert(PlayButton)
         .with_children(|parent| {
             parent.spawn_bundle(TextBundle {
                 text: Text {
@@ -88,7 +244,9 @@ fn setup_menu(
                     ..default()
                 }),
             );
-        });
+        })
+        .id();
+    ui_root.add(&mut commands, entity);
 }
 
 #[allow(clippy::type_complexity)]
@@ -97,13 +255,15 @@ fn click_play_button(
     mut state: ResMut<State<GameState>>,
     mut interaction_query: Query<
         (&Interaction, &mut UiColor),
-        (Changed<Interaction>, With<Button>),
+        (Changed<Interaction>, With<PlayButton>),
     >,
 ) {
     for (interaction, mut color) in &mut interaction_query {
         match *interaction {
             Interaction::Clicked => {
-                state.set(GameState::Playing).unwrap();
+                if let Err(e) = state.set(GameState::Playing) {
+                    debug!("ignoring play click: {e:?}");
+                }
             }
             Interaction::Hovered => {
                 *color = button_colors.hovered;
@@ -114,3 +274,96 @@ fn click_play_button(
         }
     }
 }
+
+#[allow(clippy::type_complexity)]
+fn click_screen_button(
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<(&Interaction, &mut UiColor, &ScreenButton), Changed<Interaction>>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                if let Err(e) = state.set(button.0.clone()) {
+                    debug!("ignoring menu click: {e:?}");
+                }
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        }
+    }
+}
+
+/// 选中的难度按钮高亮显示
+fn click_difficulty_button(
+    button_colors: Res<ButtonColors>,
+    mut difficulty: ResMut<Difficulty>,
+    mut buttons: Query<(&Interaction, &mut UiColor, &DifficultyButton)>,
+) {
+    for (interaction, _, button) in &buttons {
+        if *interaction == Interaction::Clicked && *difficulty != button.0 {
+            *difficulty = button.0;
+        }
+    }
+    for (interaction, mut color, button) in &mut buttons {
+        let next = if button.0 == *difficulty {
+            button_colors.selected
+        } else if *interaction == Interaction::Hovered {
+            button_colors.hovered
+        } else {
+            button_colors.normal
+        };
+        if color.0 != next.0 {
+            *color = next;
+        }
+    }
+}
+
+/// 依次切换无尽模式和每个关卡，按钮上显示当前的选择
+#[allow(clippy::type_complexity)]
+fn click_level_button(
+    button_colors: Res<ButtonColors>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+    mut selected: ResMut<SelectedLevel>,
+    mut interaction_query: Query<
+        (&Interaction, &mut UiColor),
+        (Changed<Interaction>, With<LevelButton>),
+    >,
+    mut texts: Query<&mut Text, With<LevelButtonText>>,
+) {
+    let count = level_assets.levels.len();
+    for (interaction, mut color) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                selected.0 = match selected.0 {
+                    None if count > 0 => Some(0),
+                    Some(i) if i + 1 < count => Some(i + 1),
+                    _ => None,
+                };
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        }
+    }
+    let label = selected
+        .0
+        .and_then(|i| levels.get(level_assets.levels.get(i)?))
+        .map_or_else(
+            || "Mode: Endless".to_owned(),
+            |level| format!("Level: {}", level.name),
+        );
+    for mut text in &mut texts {
+        if text.sections[0].value != label {
+            text.sections[0].value.clone_from(&label);
+        }
+    }
+}
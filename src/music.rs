@@ -0,0 +1,128 @@
+//! 背景音乐：菜单和游戏中各有一首循环播放的曲子，切换状态时交叉淡入淡出
+//!
+//! 播放音效时可以发送 [`DuckMusic`] 临时压低音乐，音乐的总音量由 [`Music`] 声道控制
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+use crate::loading::AudioAssets;
+use crate::GameState;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_channel::<Music>()
+            .add_event::<DuckMusic>()
+            .init_resource::<MusicPlayer>()
+            .add_system(play_state_music)
+            .add_system(duck_music.after(play_state_music));
+    }
+}
+
+/// 背景音乐的声道
+pub struct Music;
+
+/// 压低音乐一段时间，比如播放游戏结束的音效时
+pub struct DuckMusic(pub Duration);
+
+const CROSSFADE: Duration = Duration::from_millis(1500);
+/// 被压低时的音量
+const DUCK_VOLUME: f64 = 0.25;
+const DUCK_FADE_OUT: Duration = Duration::from_millis(150);
+const DUCK_FADE_IN: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Track {
+    Menu,
+    Gameplay,
+}
+
+impl Track {
+    /// 每个状态播放的曲子，游戏结束后继续播放游戏中的曲子，重来时不用切换
+    const fn for_state(state: &GameState) -> Option<Self> {
+        match state {
+            GameState::Loading => None,
+            GameState::Menu | GameState::Controls | GameState::Settings => Some(Track::Menu),
+            GameState::Playing | GameState::Paused | GameState::GameOver => Some(Track::Gameplay),
+        }
+    }
+
+    fn source(self, audio_assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            Track::Menu => audio_assets.menu_music.clone(),
+            Track::Gameplay => audio_assets.game_music.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct MusicPlayer {
+    track: Option<Track>,
+    instance: Option<Handle<AudioInstance>>,
+    /// 压低音乐的剩余时间，结束后恢复原来的音量
+    duck: Option<Timer>,
+}
+
+fn play_state_music(
+    state: Res<State<GameState>>,
+    music: Res<AudioChannel<Music>>,
+    audio_assets: Option<Res<AudioAssets>>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+    mut player: ResMut<MusicPlayer>,
+) {
+    let Some(audio_assets) = audio_assets else {
+        return;
+    };
+    let track = Track::for_state(state.current());
+    if track == player.track {
+        return;
+    }
+    if let Some(instance) = player
+        .instance
+        .take()
+        .and_then(|handle| instances.get_mut(&handle))
+    {
+        instance.stop(AudioTween::linear(CROSSFADE));
+    }
+    player.track = track;
+    player.duck = None;
+    player.instance = track.map(|track| {
+        music
+            .play(track.source(&audio_assets))
+            .looped()
+            .linear_fade_in(CROSSFADE)
+            .handle()
+    });
+}
+
+fn duck_music(
+    time: Res<Time>,
+    mut events: EventReader<DuckMusic>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+    mut player: ResMut<MusicPlayer>,
+) {
+    let player = &mut *player;
+    let instance = player
+        .instance
+        .as_ref()
+        .and_then(|handle| instances.get_mut(handle));
+    if let Some(DuckMusic(duration)) = events.iter().last() {
+        if let Some(instance) = instance {
+            instance.set_volume(DUCK_VOLUME, AudioTween::linear(DUCK_FADE_OUT));
+        }
+        player.duck = Some(Timer::new(*duration, false));
+        return;
+    }
+    let Some(duck) = &mut player.duck else {
+        return;
+    };
+    if duck.tick(time.delta()).finished() {
+        if let Some(instance) = instance {
+            instance.set_volume(1.0, AudioTween::linear(DUCK_FADE_IN));
+        }
+        player.duck = None;
+    }
+}
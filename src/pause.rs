@@ -0,0 +1,156 @@
+//! 暂停：按下暂停键或者窗口失去焦点时暂停，显示继续、重来、回到菜单三个按钮
+//!
+//! 暂停是压在 [`GameState::Playing`] 上面的状态，恢复时不会触发 `on_enter(Playing)`，
+//! 所以场景里的实体原样保留
+
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+
+use crate::bindings::{Action, ActionInput};
+use crate::common::clear_entities;
+use crate::letterbox::UiRoot;
+use crate::loading::FontAssets;
+use crate::menu::ButtonColors;
+use crate::GameState;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Playing).with_system(pause))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(setup_pause_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused)
+                    .with_system(resume)
+                    .with_system(click_pause_button),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused).with_system(clear_entities::<PauseMenu>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Clone, Copy)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Quit,
+}
+
+fn pause(
+    mut actions: ActionInput,
+    mut focused: EventReader<WindowFocused>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let lost_focus = focused.iter().any(|e| !e.focused);
+    // 清掉这次按键，否则同一帧里 Paused 的系统会立刻恢复
+    if actions.take(Action::Pause) || lost_focus {
+        // 同一帧里这一局已经结束时，已经排好的状态切换优先，不再暂停
+        if let Err(e) = state.push(GameState::Paused) {
+            debug!("not pausing: {e:?}");
+        }
+    }
+}
+
+fn resume(mut actions: ActionInput, mut state: ResMut<State<GameState>>) {
+    // 两个都要清掉，所以不能用 `||` 短路
+    if actions.take(Action::Pause) | actions.take(Action::Back) {
+        // 同一帧里已经点了按钮时以按钮为准
+        if let Err(e) = state.pop() {
+            debug!("not resuming: {e:?}");
+        }
+    }
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    ui_root: Res<UiRoot>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text_style = |font_size: f32| TextStyle {
+        font: font_assets.xolonium_regular.clone(),
+        font_size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let entity = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..default()
+        })
+        .insert(PauseMenu)
+        .with_children(|parent| {
+            parent.spawn_bundle(
+                TextBundle::from_section("Paused", text_style(60.0)).with_style(Style {
+                    margin: UiRect {
+                        bottom: Val::Px(40.0),
+                        ..default()
+                    },
+                    ..default()
+                }),
+            );
+            for (button, label) in [
+                (PauseButton::Resume, "Resume"),
+                (PauseButton::Restart, "Restart"),
+                (PauseButton::Quit, "Quit to menu"),
+            ] {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(260.0), Val::Px(70.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: button_colors.normal,
+                        ..default()
+                    })
+                    .insert(button)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle::from_section(label, text_style(36.0)));
+                    });
+            }
+        })
+        .id();
+    ui_root.add(&mut commands, entity);
+}
+
+#[allow(clippy::type_complexity)]
+fn click_pause_button(
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<(&Interaction, &mut UiColor, &PauseButton), Changed<Interaction>>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                let result = match button {
+                    PauseButton::Resume => state.pop(),
+                    PauseButton::Restart => state.replace(GameState::Playing),
+                    PauseButton::Quit => state.replace(GameState::Menu),
+                };
+                if let Err(e) = result {
+                    debug!("ignoring pause menu click: {e:?}");
+                }
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        }
+    }
+}
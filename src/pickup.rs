@@ -0,0 +1,385 @@
+//! 道具：每隔一段时间在游戏区域里出现一个，玩家碰到就捡起，效果持续一段时间
+//!
+//! - 护盾：挡住一次碰撞，撞上来的敌人消失
+//! - 减速：所有敌人的时间变慢，包括移动、转向和加速
+//! - 缩小：玩家变小，胶囊碰撞体跟着变小
+//!
+//! 道具是传感器碰撞体，只和玩家产生碰撞事件，见 [`CollisionRole::Pickup`]
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::Arena;
+use crate::collision::{resolve_collisions, CollisionRole, PlayerTouchedPickup};
+use crate::common::clear_entities;
+use crate::common::rng::GameRng;
+use crate::common::tick::{FixedTick, TickAppExt, TickStage};
+use crate::config::GameConfig;
+use crate::player::{player_hit, Player, PLAYER_SCALE};
+use crate::waves::direct_waves;
+use crate::GameState;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerUps>()
+            .init_resource::<PickupTimer>()
+            .add_event::<PickupEvent>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(clear_entities::<Pickup>)
+                    .with_system(reset_pickups),
+            )
+            .add_tick_system(TickStage::PreUpdate, tick_power_ups)
+            .add_tick_system(TickStage::Update, spawn_pickups.after(direct_waves))
+            .add_tick_system(
+                TickStage::PostUpdate,
+                collect_pickups.after(resolve_collisions).before(player_hit),
+            );
+    }
+}
+
+/// 给道具加上图形，给有护盾的玩家换个颜色，无头模式下不需要
+pub struct PickupSpritePlugin;
+
+impl Plugin for PickupSpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_pickup_materials)
+            .add_system_set_to_stage(
+                CoreStage::PostUpdate,
+                SystemSet::on_update(GameState::Playing).with_system(attach_pickup_sprite),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(tint_player));
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum PowerUp {
+    Shield,
+    SlowTime,
+    Shrink,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 3] = [PowerUp::Shield, PowerUp::SlowTime, PowerUp::Shrink];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            PowerUp::Shield => "Shield",
+            PowerUp::SlowTime => "Slow",
+            PowerUp::Shrink => "Shrink",
+        }
+    }
+
+    #[must_use]
+    pub const fn color(self) -> Color {
+        match self {
+            PowerUp::Shield => Color::CYAN,
+            PowerUp::SlowTime => Color::rgb(0.7, 0.4, 1.0),
+            PowerUp::Shrink => Color::rgb(0.4, 1.0, 0.4),
+        }
+    }
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 一种道具效果的参数
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct PowerUpConfig {
+    /// 持续的秒数
+    pub duration: f32,
+    /// 减速时敌人时间流速的倍数，缩小时玩家大小的倍数，护盾不使用
+    #[serde(default = "one")]
+    pub factor: f32,
+}
+
+const fn one() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PickupConfig {
+    /// 出现的间隔，单位秒
+    pub interval: f32,
+    /// 没被捡起时多少秒后消失
+    pub lifetime: f32,
+    pub radius: f32,
+    pub shield: PowerUpConfig,
+    pub slow_time: PowerUpConfig,
+    pub shrink: PowerUpConfig,
+}
+
+impl Default for PickupConfig {
+    fn default() -> Self {
+        Self {
+            interval: 12.0,
+            lifetime: 6.0,
+            radius: 16.0,
+            shield: PowerUpConfig {
+                duration: 10.0,
+                factor: 1.0,
+            },
+            slow_time: PowerUpConfig {
+                duration: 5.0,
+                factor: 0.5,
+            },
+            shrink: PowerUpConfig {
+                duration: 6.0,
+                factor: 0.6,
+            },
+        }
+    }
+}
+
+impl PickupConfig {
+    /// 间隔、存在时间、大小、每种效果的时长和倍数都是有限的正数
+    ///
+    /// # Errors
+    ///
+    /// 返回第一个无效的值
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [
+            ("pickups.interval", self.interval),
+            ("pickups.lifetime", self.lifetime),
+            ("pickups.radius", self.radius),
+            ("pickups.shield.duration", self.shield.duration),
+            ("pickups.slow_time.duration", self.slow_time.duration),
+            ("pickups.slow_time.factor", self.slow_time.factor),
+            ("pickups.shrink.duration", self.shrink.duration),
+            ("pickups.shrink.factor", self.shrink.factor),
+        ];
+        for (name, value) in values {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(format!("{name}: must be positive, got {value}"));
+            }
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub const fn get(&self, kind: PowerUp) -> &PowerUpConfig {
+        match kind {
+            PowerUp::Shield => &self.shield,
+            PowerUp::SlowTime => &self.slow_time,
+            PowerUp::Shrink => &self.shrink,
+        }
+    }
+}
+
+/// 场上的道具，`remaining` 是消失前剩余的秒数
+#[derive(Component)]
+pub struct Pickup {
+    remaining: f32,
+}
+
+/// 玩家捡起了一个道具
+pub struct PickupEvent {
+    pub position: Vec2,
+}
+
+#[derive(Deref, DerefMut)]
+struct PickupTimer(Timer);
+
+impl Default for PickupTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(12.0, true))
+    }
+}
+
+/// 正在生效的道具效果
+#[derive(Default)]
+pub struct PowerUps {
+    /// 每种效果剩余的秒数
+    remaining: [f32; 3],
+    /// 捡起时配置里的倍数
+    factors: [f32; 3],
+}
+
+impl PowerUps {
+    #[must_use]
+    pub const fn remaining(&self, kind: PowerUp) -> f32 {
+        self.remaining[kind.index()]
+    }
+
+    #[must_use]
+    pub fn is_active(&self, kind: PowerUp) -> bool {
+        self.remaining(kind) > 0.0
+    }
+
+    fn factor(&self, kind: PowerUp) -> f32 {
+        if self.is_active(kind) {
+            self.factors[kind.index()]
+        } else {
+            1.0
+        }
+    }
+
+    /// 敌人的时间流速
+    #[must_use]
+    pub fn enemy_time_scale(&self) -> f32 {
+        self.factor(PowerUp::SlowTime)
+    }
+
+    /// 玩家的大小，正常是 1
+    #[must_use]
+    pub fn player_scale(&self) -> f32 {
+        self.factor(PowerUp::Shrink)
+    }
+
+    /// 有护盾时用掉它，返回 true 表示这次碰撞被挡住了
+    pub fn take_shield(&mut self) -> bool {
+        let active = self.is_active(PowerUp::Shield);
+        self.remaining[PowerUp::Shield.index()] = 0.0;
+        active
+    }
+
+    /// 重复捡起同一种道具时重新计时
+    const fn activate(&mut self, kind: PowerUp, config: PowerUpConfig) {
+        self.remaining[kind.index()] = config.duration;
+        self.factors[kind.index()] = config.factor;
+    }
+}
+
+fn reset_pickups(
+    config: Res<GameConfig>,
+    mut timer: ResMut<PickupTimer>,
+    mut power_ups: ResMut<PowerUps>,
+) {
+    timer.set_duration(Duration::from_secs_f32(config.pickups.interval));
+    timer.reset();
+    *power_ups = PowerUps::default();
+}
+
+fn spawn_pickups(
+    mut commands: Commands,
+    tick: Res<FixedTick>,
+    config: Res<GameConfig>,
+    arena: Res<Arena>,
+    mut rng: ResMut<GameRng>,
+    mut timer: ResMut<PickupTimer>,
+) {
+    timer.set_duration(Duration::from_secs_f32(config.pickups.interval));
+    if !timer.tick(tick.delta()).just_finished() {
+        return;
+    }
+    let rng = &mut **rng;
+    let kind = PowerUp::ALL[rng.gen_range(0..PowerUp::ALL.len())];
+    let position = arena.random_point_inside(config.pickups.radius * 2.0, rng);
+    commands
+        .spawn_bundle(TransformBundle::from_transform(
+            Transform::from_translation(position.extend(0.5)),
+        ))
+        .insert(Pickup {
+            remaining: config.pickups.lifetime,
+        })
+        .insert(kind)
+        .insert(Collider::ball(config.pickups.radius))
+        .insert(Sensor)
+        .insert_bundle(CollisionRole::Pickup.bundle());
+}
+
+/// 效果和场上的道具倒计时，并按当前的效果调整玩家的大小
+fn tick_power_ups(
+    mut commands: Commands,
+    tick: Res<FixedTick>,
+    mut power_ups: ResMut<PowerUps>,
+    mut pickups: Query<(Entity, &mut Pickup)>,
+    mut players: Query<&mut Transform, With<Player>>,
+) {
+    let dt = tick.delta_seconds();
+    for remaining in &mut power_ups.remaining {
+        *remaining = (*remaining - dt).max(0.0);
+    }
+    for (entity, mut pickup) in &mut pickups {
+        pickup.remaining -= dt;
+        if pickup.remaining <= 0.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+    let scale = Vec3::splat(PLAYER_SCALE * power_ups.player_scale());
+    for mut transform in &mut players {
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
+
+pub fn collect_pickups(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut events: EventReader<PlayerTouchedPickup>,
+    mut power_ups: ResMut<PowerUps>,
+    pickups: Query<(&PowerUp, &Transform), With<Pickup>>,
+    mut collected: EventWriter<PickupEvent>,
+) {
+    for event in events.iter() {
+        let Ok((&kind, transform)) = pickups.get(event.pickup) else {
+            continue;
+        };
+        power_ups.activate(kind, *config.pickups.get(kind));
+        collected.send(PickupEvent {
+            position: transform.translation.truncate(),
+        });
+        commands.entity(event.pickup).despawn();
+    }
+}
+
+struct PickupMaterials {
+    mesh: Mesh2dHandle,
+    materials: [Handle<ColorMaterial>; 3],
+}
+
+fn setup_pickup_materials(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mesh = meshes.add(shape::Circle::new(config.pickups.radius).into());
+    commands.insert_resource(PickupMaterials {
+        mesh: mesh.into(),
+        materials: PowerUp::ALL.map(|kind| materials.add(kind.color().into())),
+    });
+}
+
+fn attach_pickup_sprite(
+    mut commands: Commands,
+    pickup_materials: Res<PickupMaterials>,
+    query: Query<(Entity, &PowerUp, &Transform), Added<Pickup>>,
+) {
+    for (entity, &kind, &transform) in &query {
+        commands.entity(entity).insert_bundle(MaterialMesh2dBundle {
+            mesh: pickup_materials.mesh.clone(),
+            material: pickup_materials.materials[kind.index()].clone(),
+            transform,
+            ..default()
+        });
+    }
+}
+
+/// 有护盾时玩家带上护盾的颜色
+fn tint_player(
+    power_ups: Res<PowerUps>,
+    mut players: Query<&mut TextureAtlasSprite, With<Player>>,
+) {
+    let color = if power_ups.is_active(PowerUp::Shield) {
+        PowerUp::Shield.color()
+    } else {
+        Color::WHITE
+    };
+    for mut sprite in &mut players {
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
@@ -1,84 +1,160 @@
 use benimator::FrameRate;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::audio::GameOverEvent;
+use crate::arena::Arena;
+use crate::collision::{resolve_collisions, CollisionRole, PlayerHit};
 use crate::common::animation::{animate, Animation, AnimationState};
 use crate::common::clear_entities;
+use crate::common::tick::{begin_interpolation, FixedTick, Interpolated, TickAppExt, TickStage};
+use crate::config::GameConfig;
+use crate::input::read_player_input;
+use crate::lives::{make_invulnerable, Invulnerable, LifeLostEvent, Lives};
 use crate::loading::PlayerTextureAtlas;
+use crate::pickup::PowerUps;
 use crate::GameState;
 
+/// 玩家贴图和碰撞体的缩放
+pub const PLAYER_SCALE: f32 = 0.5;
+
+pub struct GameOverEvent;
+
+/// 护盾挡住了一次碰撞，撞上来的敌人已经被移除，`position` 是敌人的位置
+pub struct ShieldBreakEvent {
+    pub position: Vec2,
+}
+
+/// 玩家这一帧的操作，由 [`read_player_input`] 或回放写入
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerInput {
+    pub movement: Vec2,
+}
+
 pub struct PlayerPlugin;
 
 #[derive(Component)]
 pub struct Player {
     speed: f32,
+    /// 本帧的移动方向，静止时为零
+    movement: Vec2,
+    /// 被撞开的速度，逐渐减小到零
+    knockback: Vec2,
 }
 
+/// 被撞开的速度每秒衰减的比例，越大停得越快
+const KNOCKBACK_DAMPING: f32 = 8.0;
+/// 无敌时每秒闪烁的次数
+const BLINK_RATE: f32 = 8.0;
+
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_enter(GameState::Playing)
-                .with_system(clear_entities::<Player>.before(spawn_player))
-                .with_system(spawn_player),
+        app.init_resource::<PlayerInput>()
+            .add_event::<GameOverEvent>()
+            .add_event::<ShieldBreakEvent>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(clear_entities::<Player>.before(spawn_player))
+                    .with_system(spawn_player),
+            )
+            .add_tick_system(
+                TickStage::PreUpdate,
+                read_player_input.after(begin_interpolation),
+            )
+            .add_tick_system(TickStage::Update, move_player)
+            .add_tick_system(TickStage::PostUpdate, player_hit.after(resolve_collisions));
+    }
+}
+
+/// 给玩家加上贴图和动画，无头模式下不需要
+pub struct PlayerSpritePlugin;
+
+impl Plugin for PlayerSpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set_to_stage(
+            CoreStage::PostUpdate,
+            SystemSet::on_update(GameState::Playing).with_system(attach_player_sprite),
         )
         .add_system_set(
             SystemSet::on_update(GameState::Playing)
-                .with_system(keyboard_move_player.before(animate))
-                .with_system(collision_event),
+                .with_system(update_player_sprite.before(animate))
+                .with_system(blink_player),
         );
     }
 }
 
-pub fn spawn_player(mut commands: Commands, player_assets: Res<PlayerTextureAtlas>) {
+pub fn spawn_player(mut commands: Commands, config: Res<GameConfig>) {
     commands
-        .spawn_bundle(SpriteSheetBundle {
-            transform: Transform {
-                translation: Vec3::new(0., 0., 1.),
-                scale: Vec3::new(0.5, 0.5, 0.5),
-                ..Default::default()
-            },
-            texture_atlas: player_assets.up.clone(),
+        .spawn_bundle(TransformBundle::from_transform(Transform {
+            translation: Vec3::new(0., 0., 1.),
+            scale: Vec3::splat(PLAYER_SCALE),
             ..Default::default()
+        }))
+        .insert(Player {
+            speed: config.player.speed,
+            movement: Vec2::ZERO,
+            knockback: Vec2::ZERO,
         })
-        .insert(Animation::from_indices(0..2, FrameRate::from_fps(5.0)))
-        .insert(AnimationState::default())
-        .insert(Player { speed: 400. })
-        .insert(RigidBody::Dynamic)
+        .insert(Interpolated::default())
+        .insert(RigidBody::KinematicPositionBased)
         .insert(Collider::capsule_y(10.0, 40.0))
+        .insert_bundle(CollisionRole::Player.bundle())
         .insert(ActiveEvents::COLLISION_EVENTS)
-        .insert(LockedAxes::ROTATION_LOCKED);
+        // 默认不检测运动学刚体之间以及和固定的碰撞体之间的碰撞
+        .insert(ActiveCollisionTypes::KINEMATIC_KINEMATIC | ActiveCollisionTypes::KINEMATIC_STATIC);
+}
+
+fn attach_player_sprite(
+    mut commands: Commands,
+    player_assets: Res<PlayerTextureAtlas>,
+    query: Query<Entity, Added<Player>>,
+) {
+    for entity in &query {
+        commands
+            .entity(entity)
+            .insert_bundle((
+                TextureAtlasSprite::default(),
+                player_assets.up.clone(),
+                Visibility::default(),
+                ComputedVisibility::default(),
+            ))
+            .insert(Animation::from_indices(0..2, FrameRate::from_fps(5.0)))
+            .insert(AnimationState::default());
+    }
 }
 
-fn keyboard_move_player(
-    time: Res<Time>,
-    keyboard_input: Res<Input<KeyCode>>,
+pub fn move_player(
+    tick: Res<FixedTick>,
+    input: Res<PlayerInput>,
+    arena: Res<Arena>,
+    mut player_query: Query<(&mut Transform, &mut Player)>,
+) {
+    for (mut player_transform, mut player) in &mut player_query {
+        let dt = tick.delta_seconds();
+        let movement = input.movement;
+        player.movement = movement;
+        // 不动的时候也要限制，游戏区域可能缩小了
+        let position = player_transform.translation.truncate()
+            + (movement * player.speed + player.knockback) * dt;
+        player.knockback *= (-KNOCKBACK_DAMPING * dt).exp();
+        let position = arena.clamp(position);
+        if position != player_transform.translation.truncate() {
+            player_transform.translation = position.extend(player_transform.translation.z);
+        }
+    }
+}
+
+fn update_player_sprite(
     player_assets: Res<PlayerTextureAtlas>,
     mut player_query: Query<(
-        &mut Transform,
-        &mut AnimationState,
         &Player,
+        &mut AnimationState,
         &mut TextureAtlasSprite,
         &mut Handle<TextureAtlas>,
     )>,
 ) {
-    for (mut player_transform, mut animation, player, mut sprite, mut texture_atlas) in
-        &mut player_query
-    {
-        let mut movement = Vec2::ZERO;
-        if keyboard_input.pressed(KeyCode::Up) {
-            movement.y += 1.;
-        }
-        if keyboard_input.pressed(KeyCode::Down) {
-            movement.y -= 1.;
-        }
-        if keyboard_input.pressed(KeyCode::Right) {
-            movement.x += 1.;
-        }
-        if keyboard_input.pressed(KeyCode::Left) {
-            movement.x -= 1.;
-        }
-        movement *= player.speed * time.delta_seconds();
+    for (player, mut animation, mut sprite, mut texture_atlas) in &mut player_query {
+        let movement = player.movement;
         if movement == Vec2::ZERO {
             animation.stop();
         } else {
@@ -95,24 +171,63 @@ fn keyboard_move_player(
                 sprite.flip_y = false;
                 sprite.flip_x = movement.x < 0.0;
             }
-            player_transform.translation = (player_transform.translation + movement.extend(0.))
-                .clamp(Vec3::new(-200., -360., 0.), Vec3::new(200., 360., 0.));
         }
     }
 }
 
-fn collision_event(
+/// 无敌时闪烁
+fn blink_player(mut query: Query<(&mut Visibility, Option<&Invulnerable>), With<Player>>) {
+    for (mut visibility, invulnerable) in &mut query {
+        #[allow(clippy::cast_possible_truncation)]
+        let visible = invulnerable.is_none_or(|i| (i.remaining() * BLINK_RATE) as i32 % 2 == 0);
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+    }
+}
+
+/// 碰到敌人时少一条命，最后一条命用完时游戏结束，有护盾时用掉护盾并移除这个敌人
+#[allow(clippy::too_many_arguments)]
+pub fn player_hit(
+    mut commands: Commands,
+    config: Res<GameConfig>,
     mut game_over: EventWriter<GameOverEvent>,
-    mut events: EventReader<CollisionEvent>,
+    mut shield_break: EventWriter<ShieldBreakEvent>,
+    mut life_lost: EventWriter<LifeLostEvent>,
+    mut hits: EventReader<PlayerHit>,
     mut state: ResMut<State<GameState>>,
+    mut tick: ResMut<FixedTick>,
+    mut power_ups: ResMut<PowerUps>,
+    mut lives: ResMut<Lives>,
+    mut players: Query<(Entity, &Transform, &mut Player)>,
+    enemies: Query<&Transform, Without<Player>>,
 ) {
-    if events
-        .iter()
-        .filter(|t| matches!(t, CollisionEvent::Started(..)))
-        .last()
-        .is_some()
-    {
+    let Ok((entity, transform, mut player)) = players.get_single_mut() else {
+        hits.clear();
+        return;
+    };
+    for hit in hits.iter() {
+        let position = enemies
+            .get(hit.enemy)
+            .map_or(Vec2::ZERO, |t| t.translation.truncate());
+        if power_ups.take_shield() {
+            shield_break.send(ShieldBreakEvent { position });
+            commands.entity(hit.enemy).despawn();
+            continue;
+        }
+        if lives.lose() {
+            let away = (transform.translation.truncate() - position).normalize_or_zero();
+            player.knockback = away * config.lives.knockback;
+            make_invulnerable(&mut commands, entity, config.lives.invulnerability);
+            life_lost.send(LifeLostEvent { position });
+            break;
+        }
         game_over.send(GameOverEvent);
-        state.set(GameState::Menu).unwrap();
+        tick.stop();
+        // 同一帧里已经排好的暂停作废，结束游戏优先
+        state.overwrite_set(GameState::GameOver).unwrap();
+        break;
     }
+    // 少了一条命或者游戏结束之后，这个 tick 里的其它碰撞不再算数
+    hits.clear();
 }
@@ -0,0 +1,285 @@
+//! 录制一局游戏（种子和每个 tick 的操作），之后原样回放
+//!
+//! 录像文件是 RON 格式，`--record <file>` 录制，`--replay <file>` 回放
+
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::arg_value;
+use crate::common::rng::{reseed_rng, GameRng, RngSeed};
+use crate::common::tick::{FixedTick, TickAppExt, TickStage};
+use crate::config::GameConfig;
+use crate::difficulty::Difficulty;
+use crate::input::read_player_input;
+use crate::player::PlayerInput;
+use crate::waves::GameMode;
+use crate::GameState;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Menu).with_system(start_replay))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(start_playback.before(reseed_rng))
+                    .with_system(start_recording.after(reseed_rng)),
+            )
+            .add_tick_system_set(
+                TickStage::PreUpdate,
+                SystemSet::new()
+                    .with_system(playback_input.after(read_player_input))
+                    .with_system(record_input.after(playback_input)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(save_recording)
+                    .with_system(finish_playback),
+            );
+    }
+}
+
+/// 一局游戏的录像：种子、tick 频率、难度、模式、配置的指纹和每个 tick 的玩家操作
+///
+/// `tick_rate` 为 0 的录像文件读取时报错
+#[derive(Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub tick_rate: NonZeroU32,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// 关卡的内容也记下来，关卡文件修改之后照样能回放
+    #[serde(default)]
+    pub mode: GameMode,
+    /// 录制时的 [`GameConfig::fingerprint`]，0 表示不知道
+    #[serde(default)]
+    pub config: u64,
+    pub inputs: Vec<PlayerInput>,
+}
+
+impl Default for Recording {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            tick_rate: FixedTick::DEFAULT_TICK_RATE,
+            difficulty: Difficulty::default(),
+            mode: GameMode::default(),
+            config: 0,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+impl Recording {
+    /// # Errors
+    ///
+    /// 文件读取失败、格式不对或者记下的关卡无效
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let s = fs::read_to_string(path)?;
+        let recording: Self =
+            ron::from_str(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let GameMode::Level(level) = &recording.mode {
+            level
+                .validate()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+        Ok(recording)
+    }
+
+    /// # Errors
+    ///
+    /// 文件写入失败
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let s = ron::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, s)
+    }
+}
+
+/// 存在这个资源时录制每一局，游戏结束时写入 `path`
+pub struct Recorder {
+    path: PathBuf,
+    recording: Recording,
+}
+
+impl Recorder {
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            recording: Recording::default(),
+        }
+    }
+
+    /// 命令行参数 `--record <file>`
+    #[must_use]
+    pub fn from_args() -> Option<Self> {
+        arg_value("--record").map(Self::new)
+    }
+}
+
+/// 存在这个资源时跳过菜单，用录像代替键盘来跑一局
+pub struct Playback {
+    recording: Recording,
+    cursor: usize,
+    started: bool,
+    active: bool,
+    /// 回放之前的种子和 tick 频率，回放结束后恢复
+    previous: Option<(Option<u64>, NonZeroU32)>,
+}
+
+impl Playback {
+    #[must_use]
+    pub const fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            cursor: 0,
+            started: false,
+            active: false,
+            previous: None,
+        }
+    }
+
+    /// 命令行参数 `--replay <file>`
+    ///
+    /// # Panics
+    ///
+    /// 录像文件读取失败
+    #[must_use]
+    pub fn from_args() -> Option<Self> {
+        arg_value("--replay").map(|path| {
+            let recording = Recording::load(&path)
+                .unwrap_or_else(|e| panic!("failed to load replay {path}: {e}"));
+            Self::new(recording)
+        })
+    }
+
+    /// 当前这一局是否正在回放
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// 还没回放的 tick 数
+    #[must_use]
+    pub const fn remaining(&self) -> usize {
+        self.recording.inputs.len() - self.cursor
+    }
+}
+
+fn start_replay(mut state: ResMut<State<GameState>>, playback: Option<Res<Playback>>) {
+    if playback.is_some_and(|p| !p.started) {
+        if let Err(e) = state.set(GameState::Playing) {
+            debug!("not starting the replay yet: {e:?}");
+        }
+    }
+}
+
+pub fn start_playback(
+    mut seed: ResMut<RngSeed>,
+    mut tick: ResMut<FixedTick>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mode: ResMut<GameMode>,
+    config: Res<GameConfig>,
+    playback: Option<ResMut<Playback>>,
+) {
+    if let Some(mut playback) = playback {
+        if !playback.started {
+            playback.started = true;
+            playback.active = true;
+            let recorded = playback.recording.config;
+            if recorded != 0 && recorded != config.fingerprint() {
+                warn!("replay was recorded with a different game config and may desync");
+            }
+            playback.previous = Some((seed.0, tick.tick_rate()));
+            seed.0 = Some(playback.recording.seed);
+            tick.set_tick_rate(playback.recording.tick_rate);
+            *difficulty = playback.recording.difficulty;
+            *mode = playback.recording.mode.clone();
+        }
+    }
+}
+
+fn playback_input(mut input: ResMut<PlayerInput>, playback: Option<ResMut<Playback>>) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    if !playback.active {
+        return;
+    }
+    if let Some(&recorded) = playback.recording.inputs.get(playback.cursor) {
+        *input = recorded;
+        playback.cursor += 1;
+    } else {
+        warn!("replay ran out of inputs before game over");
+        playback.active = false;
+    }
+}
+
+pub fn finish_playback(
+    mut seed: ResMut<RngSeed>,
+    mut tick: ResMut<FixedTick>,
+    playback: Option<ResMut<Playback>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    if let Some((previous_seed, tick_rate)) = playback.previous.take() {
+        seed.0 = previous_seed;
+        tick.set_tick_rate(tick_rate);
+    }
+    if !playback.active {
+        return;
+    }
+    playback.active = false;
+    if playback.remaining() == 0 {
+        info!("replay finished after {} ticks", playback.cursor);
+    } else {
+        warn!(
+            "replay desynced: game over at tick {} of {}",
+            playback.cursor,
+            playback.recording.inputs.len()
+        );
+    }
+}
+
+fn start_recording(
+    rng: Res<GameRng>,
+    tick: Res<FixedTick>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    config: Res<GameConfig>,
+    recorder: Option<ResMut<Recorder>>,
+) {
+    if let Some(mut recorder) = recorder {
+        recorder.recording = Recording {
+            seed: rng.seed(),
+            tick_rate: tick.tick_rate(),
+            difficulty: *difficulty,
+            mode: mode.clone(),
+            config: config.fingerprint(),
+            inputs: Vec::new(),
+        };
+    }
+}
+
+fn record_input(input: Res<PlayerInput>, recorder: Option<ResMut<Recorder>>) {
+    if let Some(mut recorder) = recorder {
+        recorder.recording.inputs.push(*input);
+    }
+}
+
+fn save_recording(recorder: Option<Res<Recorder>>) {
+    if let Some(recorder) = recorder {
+        match recorder.recording.save(&recorder.path) {
+            Ok(()) => info!("recording saved to {}", recorder.path.display()),
+            Err(e) => error!(
+                "failed to save recording to {}: {e}",
+                recorder.path.display()
+            ),
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! 得分：存活的时间，加上擦身而过和捡道具的奖励
+//!
+//! 敌人进入玩家周围 `near_miss_radius` 的范围又离开，并且没有撞上，算一次擦身而过。
+//! 间隔不超过 `streak_window` 秒的擦身而过形成连击，连击越多奖励的倍数越高，
+//! 被撞到时连击中断
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::collision::{CollisionRole, PlayerHit};
+use crate::common::clear_entities;
+use crate::common::tick::{FixedTick, TickAppExt, TickStage};
+use crate::config::GameConfig;
+use crate::lives::Invulnerable;
+use crate::loading::FontAssets;
+use crate::pickup::{collect_pickups, PickupEvent};
+use crate::player::{player_hit, Player};
+use crate::GameState;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<NearbyEnemies>()
+            .add_event::<BonusEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(clear_score))
+            .add_tick_system(TickStage::Update, update_score)
+            .add_tick_system(
+                TickStage::PostUpdate,
+                award_pickups
+                    .after(collect_pickups)
+                    .before(detect_near_misses),
+            )
+            .add_tick_system(TickStage::PostUpdate, detect_near_misses.after(player_hit));
+    }
+}
+
+/// 在得分的位置显示飘起来的分数，无头模式下不需要
+pub struct ScorePopupPlugin;
+
+impl Plugin for ScorePopupPlugin {
+    fn build(&self, app: &mut App) {
+        // 暂停时分数停在原地，一局结束时清掉
+        app.add_system_set(
+            SystemSet::on_exit(GameState::Playing).with_system(clear_entities::<ScorePopup>),
+        )
+        .add_system(spawn_popups)
+        .add_system_set(SystemSet::on_update(GameState::Playing).with_system(float_popups));
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreConfig {
+    /// 每存活一秒的得分
+    pub per_second: f32,
+    /// 敌人离玩家中心多近算擦身而过
+    pub near_miss_radius: f32,
+    /// 一次擦身而过的得分，再乘以连击的倍数
+    pub near_miss: f32,
+    /// 两次擦身而过间隔不超过这么多秒才算连击
+    pub streak_window: f32,
+    /// 每多一次连击倍数增加多少
+    pub multiplier_step: f32,
+    pub max_multiplier: f32,
+    /// 捡起一个道具的得分
+    pub pickup: f32,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        Self {
+            per_second: 1.0,
+            near_miss_radius: 70.0,
+            near_miss: 5.0,
+            streak_window: 3.0,
+            multiplier_step: 0.5,
+            max_multiplier: 4.0,
+            pickup: 10.0,
+        }
+    }
+}
+
+/// 本局的得分
+#[derive(Default)]
+pub struct Score {
+    /// 存活的秒数，难度曲线按它变化
+    pub time: f32,
+    /// 存活时间的得分
+    pub survival: f32,
+    /// 擦身而过的奖励
+    pub near_miss: f32,
+    pub near_misses: u32,
+    /// 捡道具的奖励
+    pub pickup: f32,
+    pub pickups: u32,
+    /// 当前的连击数
+    pub streak: u32,
+    pub best_streak: u32,
+    /// 上一次擦身而过之后的秒数
+    since_near_miss: f32,
+}
+
+impl Score {
+    /// 显示和排行榜使用的整数分数
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn points(&self) -> u32 {
+        (self.survival + self.near_miss + self.pickup) as u32
+    }
+
+    /// 下一次擦身而过的倍数
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn multiplier(&self, config: &ScoreConfig) -> f32 {
+        config
+            .multiplier_step
+            .mul_add(self.streak as f32, 1.0)
+            .min(config.max_multiplier)
+            .max(1.0)
+    }
+
+    const fn break_streak(&mut self) {
+        self.streak = 0;
+    }
+}
+
+/// 得到了一次奖励，`position` 是得分的位置
+pub struct BonusEvent {
+    pub kind: Bonus,
+    pub points: f32,
+    pub position: Vec2,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bonus {
+    NearMiss,
+    Pickup,
+}
+
+#[derive(Default)]
+struct NearbyEnemies {
+    /// 上一个 tick 在玩家附近的敌人，按实体排序
+    inside: Vec<Entity>,
+    /// 这一局撞上过玩家的敌人，之后离开也不算擦身而过
+    hit: Vec<Entity>,
+}
+
+fn clear_score(mut score: ResMut<Score>, mut nearby: ResMut<NearbyEnemies>) {
+    *score = Score::default();
+    *nearby = NearbyEnemies::default();
+}
+
+fn update_score(tick: Res<FixedTick>, config: Res<GameConfig>, mut score: ResMut<Score>) {
+    let dt = tick.delta_seconds();
+    score.time += dt;
+    score.survival += dt * config.score.per_second;
+    score.since_near_miss += dt;
+    if score.since_near_miss > config.score.streak_window {
+        score.break_streak();
+    }
+}
+
+fn award_pickups(
+    config: Res<GameConfig>,
+    mut score: ResMut<Score>,
+    mut pickups: EventReader<PickupEvent>,
+    mut bonuses: EventWriter<BonusEvent>,
+) {
+    for event in pickups.iter() {
+        score.pickup += config.score.pickup;
+        score.pickups += 1;
+        bonuses.send(BonusEvent {
+            kind: Bonus::Pickup,
+            points: config.score.pickup,
+            position: event.position,
+        });
+    }
+}
+
+/// 用物理引擎查询玩家周围的敌人，离开范围并且没有撞上的算擦身而过
+#[allow(clippy::too_many_arguments)]
+fn detect_near_misses(
+    config: Res<GameConfig>,
+    rapier: Res<RapierContext>,
+    mut score: ResMut<Score>,
+    mut nearby: ResMut<NearbyEnemies>,
+    mut hits: EventReader<PlayerHit>,
+    mut bonuses: EventWriter<BonusEvent>,
+    players: Query<(&Transform, Option<&Invulnerable>), With<Player>>,
+    transforms: Query<&Transform>,
+) {
+    let Ok((player, invulnerable)) = players.get_single() else {
+        hits.clear();
+        return;
+    };
+    // 撞上的敌人不算，之后也不再算
+    let count = nearby.hit.len();
+    nearby.hit.extend(hits.iter().map(|hit| hit.enemy));
+    if nearby.hit.len() > count {
+        score.break_streak();
+    }
+    let position = player.translation.truncate();
+    let mut current = Vec::new();
+    rapier.intersections_with_shape(
+        position,
+        0.0,
+        &Collider::ball(config.score.near_miss_radius),
+        CollisionRole::Player.query(CollisionRole::Enemy),
+        |entity| {
+            current.push(entity);
+            true
+        },
+    );
+    current.retain(|entity| !nearby.hit.contains(entity));
+    current.sort_unstable();
+    let previous = std::mem::replace(&mut nearby.inside, current);
+    // 无敌时穿过去的敌人也不算
+    if invulnerable.is_some() {
+        return;
+    }
+    for entity in previous {
+        if nearby.inside.binary_search(&entity).is_ok() || nearby.hit.contains(&entity) {
+            continue;
+        }
+        // 已经被移除的敌人，比如被护盾挡掉的
+        let Ok(transform) = transforms.get(entity) else {
+            continue;
+        };
+        let points = config.score.near_miss * score.multiplier(&config.score);
+        score.near_miss += points;
+        score.near_misses += 1;
+        score.streak += 1;
+        score.best_streak = score.best_streak.max(score.streak);
+        score.since_near_miss = 0.0;
+        bonuses.send(BonusEvent {
+            kind: Bonus::NearMiss,
+            points,
+            position: transform.translation.truncate(),
+        });
+    }
+}
+
+/// 飘起来的分数，`age` 是出现以来的秒数
+#[derive(Component)]
+struct ScorePopup {
+    age: f32,
+}
+
+/// 分数飘多久
+const POPUP_LIFETIME: f32 = 0.8;
+/// 每秒往上飘的像素
+const POPUP_SPEED: f32 = 60.0;
+
+fn spawn_popups(
+    mut commands: Commands,
+    font_assets: Option<Res<FontAssets>>,
+    mut bonuses: EventReader<BonusEvent>,
+) {
+    let Some(font_assets) = font_assets else {
+        bonuses.clear();
+        return;
+    };
+    for bonus in bonuses.iter() {
+        let color = match bonus.kind {
+            Bonus::NearMiss => Color::GOLD,
+            Bonus::Pickup => Color::CYAN,
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let value = format!("+{}", bonus.points.round() as i32);
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::from_section(
+                    value,
+                    TextStyle {
+                        font: font_assets.xolonium_regular.clone(),
+                        font_size: 24.0,
+                        color,
+                    },
+                )
+                .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_translation(bonus.position.extend(10.0)),
+                ..default()
+            })
+            .insert(ScorePopup { age: 0.0 });
+    }
+}
+
+fn float_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ScorePopup, &mut Transform, &mut Text)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut popup, mut transform, mut text) in &mut query {
+        popup.age += dt;
+        if popup.age >= POPUP_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.y += POPUP_SPEED * dt;
+        text.sections[0]
+            .style
+            .color
+            .set_a(1.0 - popup.age / POPUP_LIFETIME);
+    }
+}
@@ -0,0 +1,208 @@
+//! 玩家的设置，保存在用户配置目录下的 `dodge-the-creeps/settings.ron`，启动时读取
+
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::{PresentMode, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::Bindings;
+use crate::config::GameConfig;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = SettingsFile::load();
+        app.insert_resource(settings.bindings)
+            .insert_resource(settings.audio)
+            .insert_resource(settings.display)
+            .add_system(apply_display_settings)
+            .add_system_to_stage(CoreStage::Last, save_settings);
+    }
+}
+
+/// 音量，都在 0 到 1 之间，音乐和音效的实际音量还要乘上总音量
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 0.8,
+            sfx: 1.0,
+        }
+    }
+}
+
+impl AudioSettings {
+    #[must_use]
+    pub fn music_volume(&self) -> f64 {
+        f64::from(self.master * self.music)
+    }
+
+    #[must_use]
+    pub fn sfx_volume(&self) -> f64 {
+        f64::from(self.master * self.sfx)
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+}
+
+impl From<DisplayMode> for WindowMode {
+    fn from(mode: DisplayMode) -> Self {
+        match mode {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+/// 窗口大小是配置里 `window` 的几倍
+pub const RESOLUTIONS: [f32; 3] = [1.0, 1.5, 2.0];
+pub const UI_SCALES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub mode: DisplayMode,
+    pub vsync: bool,
+    /// [`RESOLUTIONS`] 里的一个
+    pub resolution: f32,
+    /// [`UI_SCALES`] 里的一个
+    pub ui_scale: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Windowed,
+            vsync: true,
+            resolution: 1.0,
+            ui_scale: 1.0,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    bindings: Bindings,
+    audio: AudioSettings,
+    display: DisplaySettings,
+}
+
+impl SettingsFile {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("dodge-the-creeps").join("settings.ron"))
+    }
+
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|s| ron::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|s| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, s).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            error!("failed to save settings to {}: {e}", path.display());
+        }
+    }
+}
+
+/// 设置有变化时写入文件
+fn save_settings(
+    bindings: Res<Bindings>,
+    audio: Res<AudioSettings>,
+    display: Res<DisplaySettings>,
+) {
+    // 三个资源是启动时一起插入的
+    let changed = bindings.is_changed() || audio.is_changed() || display.is_changed();
+    if changed && !bindings.is_added() {
+        SettingsFile {
+            bindings: bindings.clone(),
+            audio: *audio,
+            display: *display,
+        }
+        .save();
+    }
+}
+
+/// 窗口模式、垂直同步、分辨率和界面缩放
+///
+/// bevy 0.8 还不能单独缩放界面，所以改的是窗口的缩放系数，同时把窗口的逻辑大小
+/// 缩小同样的倍数，这样窗口大小不变，只有界面变大，相机自己会铺满窗口，见 [`crate::letterbox`]
+fn apply_display_settings(
+    settings: Res<DisplaySettings>,
+    config: Res<GameConfig>,
+    mut windows: ResMut<Windows>,
+) {
+    if !settings.is_changed() && !config.is_changed() {
+        return;
+    }
+    let Some(window) = windows.get_primary_mut() else {
+        return;
+    };
+    let mode = settings.mode.into();
+    if window.mode() != mode {
+        window.set_mode(mode);
+    }
+    let present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    if window.present_mode() != present_mode {
+        window.set_present_mode(present_mode);
+    }
+    window.set_scale_factor_override(Some(
+        window.backend_scale_factor() * f64::from(settings.ui_scale),
+    ));
+    let size = config.window * settings.resolution / settings.ui_scale;
+    if Vec2::new(window.requested_width(), window.requested_height()) != size {
+        window.set_resolution(size.x, size.y);
+    }
+}
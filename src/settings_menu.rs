@@ -0,0 +1,428 @@
+//! 设置界面：音量、窗口模式、分辨率、垂直同步和界面缩放，修改后立即生效
+//!
+//! 音量可以拖动滑条，也可以点两边的按钮，其他选项点两边的按钮切换
+
+use bevy::prelude::*;
+
+use crate::bindings::{Action, ActionInput};
+use crate::common::clear_entities;
+use crate::config::GameConfig;
+use crate::letterbox::UiRoot;
+use crate::loading::FontAssets;
+use crate::menu::ButtonColors;
+use crate::settings::{AudioSettings, DisplayMode, DisplaySettings, RESOLUTIONS, UI_SCALES};
+use crate::GameState;
+
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Settings).with_system(setup_settings_menu),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Settings)
+                .with_system(click_settings_button)
+                .with_system(drag_slider)
+                .with_system(leave_settings)
+                .with_system(
+                    show_settings
+                        .after(click_settings_button)
+                        .after(drag_slider),
+                ),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Settings).with_system(clear_entities::<SettingsMenu>),
+        );
+    }
+}
+
+/// 点一下按钮音量变化的幅度
+const VOLUME_STEP: f32 = 0.1;
+/// 拖动滑条时音量按这个取整
+const VOLUME_SNAP: f32 = 0.05;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Mode,
+    Resolution,
+    Vsync,
+    UiScale,
+}
+
+impl Setting {
+    const ALL: [Setting; 7] = [
+        Setting::MasterVolume,
+        Setting::MusicVolume,
+        Setting::SfxVolume,
+        Setting::Mode,
+        Setting::Resolution,
+        Setting::Vsync,
+        Setting::UiScale,
+    ];
+
+    const fn name(self) -> &'static str {
+        match self {
+            Setting::MasterVolume => "Master",
+            Setting::MusicVolume => "Music",
+            Setting::SfxVolume => "Sound",
+            Setting::Mode => "Window",
+            Setting::Resolution => "Size",
+            Setting::Vsync => "VSync",
+            Setting::UiScale => "UI scale",
+        }
+    }
+
+    const fn is_volume(self) -> bool {
+        matches!(
+            self,
+            Setting::MasterVolume | Setting::MusicVolume | Setting::SfxVolume
+        )
+    }
+
+    const fn volume(self, audio: &AudioSettings) -> Option<f32> {
+        match self {
+            Setting::MasterVolume => Some(audio.master),
+            Setting::MusicVolume => Some(audio.music),
+            Setting::SfxVolume => Some(audio.sfx),
+            _ => None,
+        }
+    }
+
+    const fn volume_mut(self, audio: &mut AudioSettings) -> Option<&mut f32> {
+        match self {
+            Setting::MasterVolume => Some(&mut audio.master),
+            Setting::MusicVolume => Some(&mut audio.music),
+            Setting::SfxVolume => Some(&mut audio.sfx),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn label(self, audio: &AudioSettings, display: &DisplaySettings, window: Vec2) -> String {
+        let percent = |value: f32| format!("{}%", (value * 100.0).round() as i32);
+        match self {
+            Setting::MasterVolume => percent(audio.master),
+            Setting::MusicVolume => percent(audio.music),
+            Setting::SfxVolume => percent(audio.sfx),
+            Setting::Mode => display.mode.name().to_owned(),
+            Setting::Resolution => {
+                let size = (window * display.resolution).round();
+                format!("{}x{}", size.x, size.y)
+            }
+            Setting::Vsync => if display.vsync { "On" } else { "Off" }.to_owned(),
+            Setting::UiScale => percent(display.ui_scale),
+        }
+    }
+
+    /// 往前或者往后调一档
+    fn step(self, forward: bool, audio: &mut AudioSettings, display: &mut DisplaySettings) {
+        let delta = if forward { 1 } else { -1 };
+        if let Some(volume) = self.volume_mut(audio) {
+            let step = if forward { VOLUME_STEP } else { -VOLUME_STEP };
+            *volume = snap_volume(*volume + step);
+            return;
+        }
+        match self {
+            Setting::Mode => {
+                let i = DisplayMode::ALL.iter().position(|&m| m == display.mode);
+                let len = DisplayMode::ALL.len();
+                let next = i.map_or(0, |i| (i + if forward { 1 } else { len - 1 }) % len);
+                display.mode = DisplayMode::ALL[next];
+            }
+            Setting::Resolution => {
+                display.resolution = step_in(&RESOLUTIONS, display.resolution, delta);
+            }
+            Setting::Vsync => display.vsync = !display.vsync,
+            Setting::UiScale => display.ui_scale = step_in(&UI_SCALES, display.ui_scale, delta),
+            _ => {}
+        }
+        // 界面放大的倍数不超过窗口放大的倍数，否则界面放不下
+        if display.ui_scale > display.resolution {
+            display.ui_scale = UI_SCALES
+                .into_iter()
+                .filter(|&scale| scale <= display.resolution)
+                .fold(UI_SCALES[0], f32::max);
+        }
+    }
+}
+
+fn snap_volume(volume: f32) -> f32 {
+    ((volume / VOLUME_SNAP).round() * VOLUME_SNAP).clamp(0.0, 1.0)
+}
+
+/// `options` 里离 `current` 最近的一项往后数 `delta` 项，到两端为止
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn step_in(options: &[f32], current: f32, delta: i32) -> f32 {
+    let i = (0..options.len())
+        .min_by(|&a, &b| {
+            (options[a] - current)
+                .abs()
+                .total_cmp(&(options[b] - current).abs())
+        })
+        .unwrap_or_default();
+    options[(i as i32 + delta).clamp(0, options.len() as i32 - 1) as usize]
+}
+
+#[derive(Component)]
+struct SettingsMenu;
+
+#[derive(Component, Clone, Copy)]
+enum SettingsButton {
+    Decrease(Setting),
+    Increase(Setting),
+    Slider(Setting),
+    Back,
+}
+
+/// 显示设置当前值的文字
+#[derive(Component)]
+struct SettingValue(Setting);
+
+/// 滑条里表示音量的那一段
+#[derive(Component)]
+struct SliderFill(Setting);
+
+fn button(width: f32, height: f32, color: UiColor) -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(width), Val::Px(height)),
+            margin: UiRect::all(Val::Px(3.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color,
+        ..default()
+    }
+}
+
+fn spawn_setting_row(
+    parent: &mut ChildBuilder,
+    font_assets: &FontAssets,
+    button_colors: &ButtonColors,
+    setting: Setting,
+) {
+    let text = |value: &str, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.xolonium_regular.clone(),
+                font_size,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+    };
+    let is_volume = setting.is_volume();
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                margin: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(text(setting.name(), 18.0).with_style(Style {
+                size: Size::new(Val::Px(90.0), Val::Auto),
+                ..default()
+            }));
+            parent
+                .spawn_bundle(button(34.0, 34.0, button_colors.normal))
+                .insert(SettingsButton::Decrease(setting))
+                .with_children(|parent| {
+                    parent.spawn_bundle(text("<", 20.0));
+                });
+            let mut value = if is_volume {
+                parent.spawn_bundle(button(130.0, 34.0, button_colors.normal))
+            } else {
+                parent.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(130.0), Val::Px(34.0)),
+                        margin: UiRect::all(Val::Px(3.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+            };
+            if is_volume {
+                value.insert(SettingsButton::Slider(setting));
+            }
+            value.with_children(|parent| {
+                if is_volume {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                position_type: PositionType::Absolute,
+                                position: UiRect {
+                                    left: Val::Px(0.0),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            color: button_colors.selected,
+                            ..default()
+                        })
+                        .insert(SliderFill(setting));
+                }
+                parent
+                    .spawn_bundle(text("", 16.0))
+                    .insert(SettingValue(setting));
+            });
+            parent
+                .spawn_bundle(button(34.0, 34.0, button_colors.normal))
+                .insert(SettingsButton::Increase(setting))
+                .with_children(|parent| {
+                    parent.spawn_bundle(text(">", 20.0));
+                });
+        });
+}
+
+fn setup_settings_menu(
+    mut commands: Commands,
+    ui_root: Res<UiRoot>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text = |value: &str, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.xolonium_regular.clone(),
+                font_size,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+    };
+    let entity = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(SettingsMenu)
+        .with_children(|parent| {
+            parent.spawn_bundle(text("Settings", 40.0));
+            for setting in Setting::ALL {
+                spawn_setting_row(parent, &font_assets, &button_colors, setting);
+            }
+            parent
+                .spawn_bundle(button(140.0, 40.0, button_colors.normal))
+                .insert(SettingsButton::Back)
+                .with_children(|parent| {
+                    parent.spawn_bundle(text("Back", 24.0));
+                });
+        })
+        .id();
+    ui_root.add(&mut commands, entity);
+}
+
+#[allow(clippy::type_complexity)]
+fn click_settings_button(
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<State<GameState>>,
+    mut audio: ResMut<AudioSettings>,
+    mut display: ResMut<DisplaySettings>,
+    mut interaction_query: Query<
+        (&Interaction, &mut UiColor, &SettingsButton),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, mut color, &button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => match button {
+                SettingsButton::Decrease(setting) => setting.step(false, &mut audio, &mut display),
+                SettingsButton::Increase(setting) => setting.step(true, &mut audio, &mut display),
+                SettingsButton::Slider(_) => {}
+                SettingsButton::Back => {
+                    if let Err(e) = state.set(GameState::Menu) {
+                        debug!("ignoring back click: {e:?}");
+                    }
+                }
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        }
+    }
+}
+
+/// 按住鼠标拖动滑条
+fn drag_slider(
+    mouse_buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut audio: ResMut<AudioSettings>,
+    sliders: Query<(&Interaction, &GlobalTransform, &Node, &SettingsButton)>,
+) {
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = windows.get_primary().and_then(Window::cursor_position) else {
+        return;
+    };
+    for (interaction, transform, node, &button) in &sliders {
+        let SettingsButton::Slider(setting) = button else {
+            continue;
+        };
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let left = transform.translation().x - node.size.x / 2.0;
+        let value = snap_volume((cursor.x - left) / node.size.x);
+        if setting.volume(&audio) != Some(value) {
+            if let Some(volume) = setting.volume_mut(&mut audio) {
+                *volume = value;
+            }
+        }
+    }
+}
+
+fn leave_settings(mut actions: ActionInput, mut state: ResMut<State<GameState>>) {
+    if actions.take(Action::Back) {
+        if let Err(e) = state.set(GameState::Menu) {
+            debug!("not leaving settings: {e:?}");
+        }
+    }
+}
+
+/// 设置有变化时更新显示的值
+fn show_settings(
+    audio: Res<AudioSettings>,
+    display: Res<DisplaySettings>,
+    config: Res<GameConfig>,
+    mut values: Query<(&mut Text, &SettingValue)>,
+    mut fills: Query<(&mut Style, &SliderFill)>,
+    added: Query<(), Added<SettingValue>>,
+) {
+    if !audio.is_changed() && !display.is_changed() && added.is_empty() {
+        return;
+    }
+    for (mut text, value) in &mut values {
+        text.sections[0].value = value.0.label(&audio, &display, config.window);
+    }
+    for (mut style, fill) in &mut fills {
+        if let Some(volume) = fill.0.volume(&audio) {
+            style.size.width = Val::Percent(volume * 100.0);
+        }
+    }
+}
@@ -0,0 +1,342 @@
+//! 音效：游戏中的事件对应配置文件 `sounds` 里的一个 [`Cue`]
+//!
+//! 发送 [`PlayCue`] 播放音效，每次随机挑一个文件，音量和音调随机浮动，
+//! 带位置的音效按横坐标在左右声道之间平移
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::Arena;
+use crate::config::GameConfig;
+use crate::enemy::EnemySpawnEvent;
+use crate::focus::Focus;
+use crate::lives::LifeLostEvent;
+use crate::music::DuckMusic;
+use crate::pickup::PickupEvent;
+use crate::player::{GameOverEvent, Player, ShieldBreakEvent};
+use crate::score::{Bonus, BonusEvent};
+use crate::GameState;
+
+pub struct SfxPlugin;
+
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_channel::<Sfx>()
+            .add_event::<PlayCue>()
+            .init_resource::<CueSounds>()
+            .init_resource::<Voices>()
+            .add_system(load_cue_sounds)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(round_start_sound))
+            .add_system(game_over_sound)
+            .add_system(enemy_spawn_sound)
+            .add_system(power_up_sound)
+            .add_system(life_lost_sound)
+            .add_system(near_miss_sound)
+            .add_system(button_sound)
+            .add_system_to_stage(CoreStage::PostUpdate, play_cues);
+    }
+}
+
+/// 音效的声道
+pub struct Sfx;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Cue {
+    GameOver,
+    /// 敌人在玩家附近出现
+    EnemySpawn,
+    ButtonHover,
+    ButtonClick,
+    /// 进入游戏，开始一局
+    RoundStart,
+    /// 捡起道具
+    PowerUp,
+    /// 护盾挡住了一次碰撞
+    ShieldBreak,
+    /// 少了一条命，还没有结束
+    LifeLost,
+    /// 敌人擦身而过
+    NearMiss,
+}
+
+/// 播放一个音效，`x` 是发出声音的位置的横坐标
+pub struct PlayCue {
+    pub cue: Cue,
+    pub x: Option<f32>,
+}
+
+impl PlayCue {
+    #[must_use]
+    pub const fn new(cue: Cue) -> Self {
+        Self { cue, x: None }
+    }
+
+    #[must_use]
+    pub const fn at(cue: Cue, x: f32) -> Self {
+        Self { cue, x: Some(x) }
+    }
+}
+
+/// 一个音效的参数
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundCue {
+    /// 相对 `assets` 目录的路径，每次随机挑一个
+    pub files: Vec<String>,
+    pub volume: f64,
+    /// 音量在 `volume` 上下随机浮动的幅度
+    pub volume_variation: f64,
+    /// 播放速度在 1 上下随机浮动的幅度，速度越快音调越高
+    pub pitch_variation: f64,
+    /// 同时播放的最大数量，已经有这么多在播放时不再播放新的
+    pub max_voices: usize,
+    /// 播放时压低背景音乐
+    pub duck_music: bool,
+}
+
+impl Default for SoundCue {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            volume: 1.0,
+            volume_variation: 0.0,
+            pitch_variation: 0.0,
+            max_voices: 4,
+            duck_music: false,
+        }
+    }
+}
+
+impl SoundCue {
+    fn new(file: &str, volume: f64, pitch_variation: f64, max_voices: usize) -> Self {
+        Self {
+            files: vec![file.to_owned()],
+            volume,
+            pitch_variation,
+            max_voices,
+            ..default()
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SoundCues(pub HashMap<Cue, SoundCue>);
+
+impl Default for SoundCues {
+    fn default() -> Self {
+        Self(HashMap::from([
+            (
+                Cue::GameOver,
+                SoundCue {
+                    duck_music: true,
+                    ..SoundCue::new("audio/gameover.wav", 1.0, 0.0, 1)
+                },
+            ),
+            (
+                Cue::EnemySpawn,
+                SoundCue::new("audio/spawn.wav", 0.5, 0.15, 3),
+            ),
+            (
+                Cue::ButtonHover,
+                SoundCue::new("audio/hover.wav", 0.4, 0.05, 1),
+            ),
+            (
+                Cue::ButtonClick,
+                SoundCue::new("audio/click.wav", 0.6, 0.0, 2),
+            ),
+            (Cue::RoundStart, SoundCue::new("audio/go.wav", 0.7, 0.0, 1)),
+            (
+                Cue::PowerUp,
+                SoundCue::new("audio/powerup.wav", 0.7, 0.05, 2),
+            ),
+            (
+                Cue::ShieldBreak,
+                SoundCue::new("audio/shield.wav", 0.8, 0.05, 2),
+            ),
+            (Cue::LifeLost, SoundCue::new("audio/hit.wav", 0.9, 0.0, 1)),
+            (
+                Cue::NearMiss,
+                SoundCue::new("audio/whoosh.wav", 0.5, 0.1, 2),
+            ),
+        ]))
+    }
+}
+
+/// 敌人离玩家这么近时才播放出现的音效
+const NEAR_SPAWN_DISTANCE: f32 = 250.0;
+/// 位于游戏区域两侧边缘时的平移量，0.5 是正中间
+const MAX_PANNING: f64 = 0.4;
+/// 刚播放的音效还没有出现在 `Assets<AudioInstance>` 里，这段时间内算作正在播放
+const QUEUED_GRACE: Duration = Duration::from_millis(250);
+
+/// 每个音效加载好的文件
+#[derive(Default)]
+struct CueSounds(HashMap<Cue, Vec<Handle<AudioSource>>>);
+
+/// 每个音效正在播放的实例和开始播放的时间
+#[derive(Default)]
+struct Voices(HashMap<Cue, Vec<(Handle<AudioInstance>, Duration)>>);
+
+fn load_cue_sounds(
+    config: Res<GameConfig>,
+    asset_server: Res<AssetServer>,
+    mut sounds: ResMut<CueSounds>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    sounds.0 = config
+        .sounds
+        .0
+        .iter()
+        .map(|(&cue, sound)| {
+            let files = sound.files.iter().map(|f| asset_server.load(f.as_str()));
+            (cue, files.collect())
+        })
+        .collect();
+}
+
+/// 音量和音调的随机浮动不使用 [`GameRng`](crate::common::rng::GameRng)，不影响录像的重放
+#[allow(clippy::too_many_arguments)]
+fn play_cues(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    arena: Res<Arena>,
+    sfx: Res<AudioChannel<Sfx>>,
+    sources: Res<Assets<AudioSource>>,
+    instances: Res<Assets<AudioInstance>>,
+    sounds: Res<CueSounds>,
+    mut voices: ResMut<Voices>,
+    mut events: EventReader<PlayCue>,
+    mut duck: EventWriter<DuckMusic>,
+) {
+    let now = time.time_since_startup();
+    let mut rng = rand::thread_rng();
+    for event in events.iter() {
+        let (Some(cue), Some(files)) = (config.sounds.0.get(&event.cue), sounds.0.get(&event.cue))
+        else {
+            continue;
+        };
+        if files.is_empty() {
+            continue;
+        }
+        let playing = voices.0.entry(event.cue).or_default();
+        playing.retain(|(handle, started)| {
+            instances.get(handle).map_or_else(
+                || now.saturating_sub(*started) < QUEUED_GRACE,
+                |instance| instance.state() != PlaybackState::Stopped,
+            )
+        });
+        if playing.len() >= cue.max_voices {
+            continue;
+        }
+        let source = files[rng.gen_range(0..files.len())].clone();
+        let vary = |rng: &mut rand::rngs::ThreadRng, variation: f64| {
+            if variation > 0.0 {
+                rng.gen_range(-variation..=variation)
+            } else {
+                0.0
+            }
+        };
+        let volume = (cue.volume + vary(&mut rng, cue.volume_variation)).max(0.0);
+        let rate = 1.0 + vary(&mut rng, cue.pitch_variation);
+        let panning = event.x.map_or(0.5, |x| {
+            let x = f64::from((x / arena.half_width()).clamp(-1.0, 1.0));
+            MAX_PANNING.mul_add(x, 0.5)
+        });
+        if cue.duck_music {
+            if let Some(source) = sources.get(&source) {
+                duck.send(DuckMusic(source.sound.duration()));
+            }
+        }
+        let handle = sfx
+            .play(source)
+            .with_volume(volume)
+            .with_playback_rate(rate)
+            .with_panning(panning)
+            .handle();
+        playing.push((handle, now));
+    }
+}
+
+fn round_start_sound(mut cues: EventWriter<PlayCue>) {
+    cues.send(PlayCue::new(Cue::RoundStart));
+}
+
+fn game_over_sound(
+    mut events: EventReader<GameOverEvent>,
+    player: Query<&Transform, With<Player>>,
+    mut cues: EventWriter<PlayCue>,
+) {
+    if events.iter().last().is_some() {
+        let x = player.get_single().map_or(0.0, |t| t.translation.x);
+        cues.send(PlayCue::at(Cue::GameOver, x));
+    }
+}
+
+fn enemy_spawn_sound(
+    mut events: EventReader<EnemySpawnEvent>,
+    player: Query<&Transform, With<Player>>,
+    mut cues: EventWriter<PlayCue>,
+) {
+    let Ok(player) = player.get_single() else {
+        events.clear();
+        return;
+    };
+    let player = player.translation.truncate();
+    for event in events.iter() {
+        if event.position.distance(player) < NEAR_SPAWN_DISTANCE {
+            cues.send(PlayCue::at(Cue::EnemySpawn, event.position.x));
+        }
+    }
+}
+
+fn power_up_sound(
+    mut pickups: EventReader<PickupEvent>,
+    mut shield_breaks: EventReader<ShieldBreakEvent>,
+    mut cues: EventWriter<PlayCue>,
+) {
+    for event in pickups.iter() {
+        cues.send(PlayCue::at(Cue::PowerUp, event.position.x));
+    }
+    for event in shield_breaks.iter() {
+        cues.send(PlayCue::at(Cue::ShieldBreak, event.position.x));
+    }
+}
+
+fn life_lost_sound(mut events: EventReader<LifeLostEvent>, mut cues: EventWriter<PlayCue>) {
+    for event in events.iter() {
+        cues.send(PlayCue::at(Cue::LifeLost, event.position.x));
+    }
+}
+
+fn near_miss_sound(mut events: EventReader<BonusEvent>, mut cues: EventWriter<PlayCue>) {
+    for event in events.iter() {
+        if event.kind == Bonus::NearMiss {
+            cues.send(PlayCue::at(Cue::NearMiss, event.position.x));
+        }
+    }
+}
+
+/// 鼠标移到按钮上或者焦点移到按钮上时播放同一个声音，一帧最多一次
+fn button_sound(
+    focus: Res<Focus>,
+    mut last_focus: Local<Option<Entity>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    mut cues: EventWriter<PlayCue>,
+) {
+    let focused = focus.entity();
+    let focus_moved = focused.is_some() && focused != *last_focus;
+    *last_focus = focused;
+    if buttons.iter().any(|i| *i == Interaction::Clicked) {
+        cues.send(PlayCue::new(Cue::ButtonClick));
+    } else if focus_moved || buttons.iter().any(|i| *i == Interaction::Hovered) {
+        cues.send(PlayCue::new(Cue::ButtonHover));
+    }
+}
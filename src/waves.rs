@@ -0,0 +1,432 @@
+//! 波次：[`WaveDirector`] 决定敌人什么时候、从哪里出现
+//!
+//! 无尽模式按难度曲线不停地生成敌人；关卡模式执行关卡文件 `assets/levels/*.level.ron`
+//! 里的脚本，按顺序生成每一波敌人，中间可以停顿，全部执行完并且敌人都离开后过关
+
+use std::f32::consts::PI;
+use std::time::Duration;
+use std::{fs, io};
+
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::Arena;
+use crate::common::check_range;
+use crate::common::path::PathPoint;
+use crate::common::rng::GameRng;
+use crate::common::tick::{FixedTick, TickAppExt, TickStage};
+use crate::difficulty::DifficultyLevel;
+use crate::enemy::{Enemy, EnemyKind, EnemySpawner};
+use crate::loading::LevelAssets;
+use crate::replay::start_playback;
+use crate::GameState;
+
+/// 游戏逻辑部分，无头模式下也需要
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<WaveDirector>()
+            .add_event::<LevelCompleteEvent>()
+            .add_event::<WaveStartEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_director))
+            .add_tick_system(TickStage::Update, direct_waves);
+    }
+}
+
+/// 加载关卡文件，以及菜单里选择关卡
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .init_resource::<SelectedLevel>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(apply_selected_level.before(start_playback)),
+            );
+    }
+}
+
+/// 所有敌人都出现过并且离开了，关卡结束
+pub struct LevelCompleteEvent;
+
+/// 关卡开始了第 `number` 波敌人，从 1 开始
+pub struct WaveStartEvent {
+    pub number: u32,
+}
+
+/// 一个关卡，按顺序执行 `steps`
+#[derive(Clone, Default, Serialize, Deserialize, TypeUuid)]
+#[uuid = "0c3f5c2e-94a5-4a0e-9d0b-6f1d0c8c4b7a"]
+pub struct Level {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Step {
+    /// 生成一波敌人，这一波全部出现之后才执行下一步
+    Wave(Wave),
+    /// 等待若干秒
+    Pause(f32),
+    /// 等到场上没有敌人
+    WaitForClear,
+}
+
+/// 一波敌人
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Wave {
+    /// 在这几种敌人里按权重随机选，为空时所有种类都可以
+    pub enemies: Vec<EnemyKind>,
+    pub count: u32,
+    /// 从出现路径的哪几段上出现，为空时整条路径，默认的矩形路径从上边开始顺时针是 0 到 3
+    pub segments: Vec<usize>,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    pub pattern: Pattern,
+}
+
+impl Level {
+    /// 不经过资源系统直接读取关卡文件，给无头模式用
+    ///
+    /// # Errors
+    ///
+    /// 文件读取失败、格式不对或者 [`validate`](Self::validate) 不通过
+    pub fn load(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        let s = fs::read_to_string(path)?;
+        let level: Self =
+            ron::from_str(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        level
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(level)
+    }
+
+    /// 速度范围有效，间隔和停顿的秒数不为负
+    ///
+    /// # Errors
+    ///
+    /// 返回第一个无效的步骤
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_seconds = |seconds: f32| seconds.is_finite() && seconds >= 0.0;
+        for (i, step) in self.steps.iter().enumerate() {
+            match step {
+                Step::Wave(wave) => {
+                    check_range(&format!("steps[{i}] speed"), wave.min_speed, wave.max_speed)?;
+                    if let Pattern::Stream { interval } = wave.pattern {
+                        if !valid_seconds(interval) {
+                            return Err(format!("steps[{i}]: invalid stream interval {interval}"));
+                        }
+                    }
+                }
+                &Step::Pause(seconds) => {
+                    if !valid_seconds(seconds) {
+                        return Err(format!("steps[{i}]: invalid pause {seconds}"));
+                    }
+                }
+                Step::WaitForClear => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self {
+            enemies: Vec::new(),
+            count: 1,
+            segments: Vec::new(),
+            min_speed: 100.0,
+            max_speed: 300.0,
+            pattern: Pattern::Burst,
+        }
+    }
+}
+
+/// 一波敌人出现的方式
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum Pattern {
+    /// 同时出现在随机的位置
+    Burst,
+    /// 每隔 `interval` 秒出现一个
+    Stream { interval: f32 },
+    /// 同时出现，沿路径等距排开，直直地朝里面前进
+    Ring,
+}
+
+/// 无尽模式还是某个关卡，录像里也会记下来
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Endless,
+    Level(Level),
+}
+
+/// 菜单里选择的关卡，`None` 是无尽模式
+#[derive(Default)]
+pub struct SelectedLevel(pub Option<usize>);
+
+/// 正在执行的步骤
+enum Running {
+    Wave {
+        wave: Wave,
+        spawned: u32,
+        /// 这一波开始以来的秒数
+        elapsed: f32,
+    },
+    Pause(f32),
+    WaitForClear,
+}
+
+impl Running {
+    fn start(step: &Step) -> Self {
+        match step {
+            Step::Wave(wave) => Running::Wave {
+                wave: wave.clone(),
+                spawned: 0,
+                elapsed: 0.0,
+            },
+            &Step::Pause(seconds) => Running::Pause(seconds),
+            Step::WaitForClear => Running::WaitForClear,
+        }
+    }
+}
+
+pub struct WaveDirector {
+    /// 无尽模式生成敌人的间隔
+    timer: Timer,
+    /// 下一个要执行的步骤
+    next_step: usize,
+    running: Option<Running>,
+    /// 已经开始的波数
+    waves_started: u32,
+    complete: bool,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.5, true),
+            next_step: 0,
+            running: None,
+            waves_started: 0,
+            complete: false,
+        }
+    }
+}
+
+impl WaveDirector {
+    /// 关卡是否已经完成
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.complete
+    }
+}
+
+fn reset_director(mut director: ResMut<WaveDirector>) {
+    *director = WaveDirector::default();
+}
+
+/// 把菜单里选择的关卡复制到 [`GameMode`]，这样修改关卡文件之后下一局就会生效
+fn apply_selected_level(
+    selected: Res<SelectedLevel>,
+    level_assets: Option<Res<LevelAssets>>,
+    levels: Res<Assets<Level>>,
+    mut mode: ResMut<GameMode>,
+) {
+    let level = selected
+        .0
+        .zip(level_assets)
+        .and_then(|(i, assets)| levels.get(assets.levels.get(i)?));
+    *mode = level.map_or(GameMode::Endless, |level| GameMode::Level(level.clone()));
+}
+
+/// 朝里面，左右最多偏 45 度
+fn inward<R: Rng + ?Sized>(point: &PathPoint, rng: &mut R) -> Vec2 {
+    (-point.normal).rotate(Vec2::from_angle(rng.gen_range(-PI / 4.0..PI / 4.0)))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn direct_waves(
+    mut tick: ResMut<FixedTick>,
+    mode: Res<GameMode>,
+    mut director: ResMut<WaveDirector>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    level: Res<DifficultyLevel>,
+    enemies: Query<(), With<Enemy>>,
+    mut spawner: EnemySpawner,
+    mut state: ResMut<State<GameState>>,
+    mut complete: EventWriter<LevelCompleteEvent>,
+    mut wave_start: EventWriter<WaveStartEvent>,
+) {
+    let rng = &mut **rng;
+    let enemy_count = enemies.iter().count();
+    match &*mode {
+        GameMode::Endless => {
+            let timer = &mut director.timer;
+            timer.set_duration(Duration::from_secs_f32(level.spawn_interval));
+            timer.tick(tick.delta());
+            if !timer.just_finished() || enemy_count >= level.max_enemies as usize {
+                return;
+            }
+            let point = arena.random_spawn_point(rng);
+            let direction = inward(&point, rng);
+            let kind = spawner.archetypes().choose(rng);
+            let speed = rng.gen_range(level.min_speed..=level.max_speed);
+            spawner.spawn(kind, point.position, direction, speed, rng);
+        }
+        GameMode::Level(script) => {
+            if director.complete {
+                return;
+            }
+            let delta = tick.delta_seconds();
+            let started = director.waves_started;
+            run_script(
+                &mut director,
+                script,
+                enemy_count,
+                delta,
+                &arena,
+                &mut spawner,
+                rng,
+            );
+            for number in started + 1..=director.waves_started {
+                wave_start.send(WaveStartEvent { number });
+            }
+            if director.complete {
+                complete.send(LevelCompleteEvent);
+                tick.stop();
+                // 覆盖掉同一帧里的暂停
+                state.overwrite_set(GameState::GameOver).unwrap();
+            }
+        }
+    }
+}
+
+/// 执行关卡的脚本，一个 tick 里可以连续完成多个步骤
+///
+/// 这个 tick 生成的敌人要到下一个 tick 才查询得到，所以自己加到 `enemy_count` 上
+fn run_script<R: Rng + ?Sized>(
+    director: &mut WaveDirector,
+    script: &Level,
+    mut enemy_count: usize,
+    delta: f32,
+    arena: &Arena,
+    spawner: &mut EnemySpawner,
+    rng: &mut R,
+) {
+    loop {
+        if director.running.is_none() {
+            let Some(step) = script.steps.get(director.next_step) else {
+                director.complete = enemy_count == 0;
+                return;
+            };
+            director.next_step += 1;
+            if matches!(step, Step::Wave(_)) {
+                director.waves_started += 1;
+            }
+            director.running = Some(Running::start(step));
+        }
+        let Some(running) = &mut director.running else {
+            return;
+        };
+        let finished = match running {
+            Running::Wave {
+                wave,
+                spawned,
+                elapsed,
+            } => {
+                let due = match wave.pattern {
+                    Pattern::Burst | Pattern::Ring => wave.count,
+                    // 第一个立刻出现
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    Pattern::Stream { interval } => {
+                        (*elapsed / interval.max(f32::EPSILON)) as u32 + 1
+                    }
+                };
+                let due = due.min(wave.count);
+                spawn_wave(wave, *spawned..due, arena, spawner, rng);
+                enemy_count += due.saturating_sub(*spawned) as usize;
+                *spawned = (*spawned).max(due);
+                *elapsed += delta;
+                *spawned >= wave.count
+            }
+            Running::Pause(remaining) => {
+                *remaining -= delta;
+                *remaining <= 0.0
+            }
+            Running::WaitForClear => enemy_count == 0,
+        };
+        if !finished {
+            return;
+        }
+        director.running = None;
+    }
+}
+
+/// 生成这一波里的第 `range` 个敌人
+fn spawn_wave<R: Rng + ?Sized>(
+    wave: &Wave,
+    range: std::ops::Range<u32>,
+    arena: &Arena,
+    spawner: &mut EnemySpawner,
+    rng: &mut R,
+) {
+    if range.is_empty() {
+        return;
+    }
+    let path = arena.spawn_path();
+    let length = path.segments_length(&wave.segments);
+    // 环形的起点随机，之后等距排开
+    let phase = rng.gen::<f32>();
+    for i in range {
+        let point = match wave.pattern {
+            Pattern::Burst | Pattern::Stream { .. } => {
+                path.sample_segments(&wave.segments, rng.gen::<f32>() * length)
+            }
+            #[allow(clippy::cast_precision_loss)]
+            Pattern::Ring => {
+                let offset = (phase + i as f32 / wave.count as f32).fract();
+                path.sample_segments(&wave.segments, offset * length)
+            }
+        };
+        let direction = match wave.pattern {
+            Pattern::Ring => -point.normal,
+            Pattern::Burst | Pattern::Stream { .. } => inward(&point, rng),
+        };
+        let kind = spawner.archetypes().choose_from(&wave.enemies, rng);
+        let speed = rng.gen_range(wave.min_speed..=wave.max_speed);
+        spawner.spawn(kind, point.position, direction, speed, rng);
+    }
+}
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let level: Level = ron::de::from_bytes(bytes)?;
+            level.validate().map_err(anyhow::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
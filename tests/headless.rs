@@ -0,0 +1,155 @@
+//! 无头模式的集成测试：按固定步长推进游戏逻辑，同一个种子跑出同样的结果，录像回放和原来一致，关卡打完才算过关
+
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use dodge_the_creeps_bevy::{
+    headless_app, Enemy, FixedTick, GameConfig, GameMode, GameState, Level, Playback, Recorder,
+    Recording, RngSeed, TickAppExt, TickStage, WaveDirector,
+};
+
+/// 一帧里最多追赶 250 毫秒的游戏时间，加速之后每帧能跑很多个 tick
+const TIME_SCALE: f32 = 1000.0;
+/// 防止游戏逻辑卡住时测试一直跑下去
+const MAX_FRAMES: u32 = 1_000_000;
+
+/// 游戏逻辑跑过的 tick 数，到 `limit` 时停下
+struct TickCount {
+    count: u32,
+    limit: u32,
+}
+
+fn count_ticks(mut ticks: ResMut<TickCount>, mut tick: ResMut<FixedTick>) {
+    ticks.count += 1;
+    if ticks.count >= ticks.limit {
+        tick.stop();
+    }
+}
+
+fn app(seed: u64) -> App {
+    let mut app = headless_app();
+    app.insert_resource(RngSeed(Some(seed)));
+    app.world
+        .resource_mut::<FixedTick>()
+        .set_time_scale(TIME_SCALE);
+    app
+}
+
+fn is_playing(app: &App) -> bool {
+    app.world.resource::<State<GameState>>().current() == &GameState::Playing
+}
+
+/// 跑 `limit` 个 tick，返回实际跑了多少个，游戏提前结束时会少一些
+fn run_ticks(app: &mut App, limit: u32) -> u32 {
+    app.insert_resource(TickCount { count: 0, limit })
+        .add_tick_system(TickStage::PostUpdate, count_ticks);
+    for _ in 0..MAX_FRAMES {
+        if app.world.resource::<TickCount>().count >= limit || !is_playing(app) {
+            break;
+        }
+        app.update();
+    }
+    app.world.resource::<TickCount>().count
+}
+
+/// 跑到游戏结束
+fn run_to_game_over(app: &mut App) {
+    for _ in 0..MAX_FRAMES {
+        if !is_playing(app) {
+            return;
+        }
+        app.update();
+    }
+    panic!("game did not end after {MAX_FRAMES} frames");
+}
+
+/// 场景里所有实体的位置，用来比较两次运行的结果
+fn positions(app: &mut App) -> Vec<[u32; 3]> {
+    let mut positions: Vec<_> = app
+        .world
+        .query::<&Transform>()
+        .iter(&app.world)
+        .map(|transform| transform.translation.to_array().map(f32::to_bits))
+        .collect();
+    positions.sort_unstable();
+    positions
+}
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("dodge-{}-{name}.ron", std::process::id()))
+}
+
+#[test]
+fn headless_app_steps_fixed_ticks() {
+    let mut app = app(1);
+    let ticks = run_ticks(&mut app, 30);
+    assert_eq!(ticks, 30);
+    assert!(is_playing(&app));
+}
+
+#[test]
+fn same_seed_gives_the_same_run() {
+    let mut a = app(42);
+    let mut b = app(42);
+    assert_eq!(run_ticks(&mut a, 90), run_ticks(&mut b, 90));
+    assert_eq!(positions(&mut a), positions(&mut b));
+}
+
+#[test]
+fn replay_matches_the_recording() {
+    let recorded = temp_file("recorded");
+    let replayed = temp_file("replayed");
+
+    let mut app = app(7);
+    app.insert_resource(Recorder::new(&recorded));
+    run_to_game_over(&mut app);
+
+    let recording = Recording::load(&recorded).expect("recording was saved");
+    let mut app = headless_app();
+    app.insert_resource(Playback::new(recording))
+        .insert_resource(Recorder::new(&replayed));
+    app.world
+        .resource_mut::<FixedTick>()
+        .set_time_scale(TIME_SCALE);
+    run_to_game_over(&mut app);
+    assert_eq!(app.world.resource::<Playback>().remaining(), 0);
+
+    let original = fs::read(&recorded).unwrap();
+    let replay = fs::read(&replayed).unwrap();
+    let _ = fs::remove_file(&recorded);
+    let _ = fs::remove_file(&replayed);
+    assert!(original == replay, "replay recorded different inputs");
+}
+
+#[test]
+fn shipped_config_and_levels_load() {
+    GameConfig::load("assets/game.config.ron").unwrap();
+    for entry in fs::read_dir("assets/levels").unwrap() {
+        let path = entry.unwrap().path();
+        Level::load(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    }
+}
+
+#[test]
+fn level_completes_once_the_last_wave_is_gone() {
+    let mut config = GameConfig::load("assets/game.config.ron").unwrap();
+    // 不让玩家死掉，一直玩到关卡结束
+    config.lives.easy = 1000;
+    config.lives.normal = 1000;
+    config.lives.hard = 1000;
+    let level = Level::load("assets/levels/first_steps.level.ron").unwrap();
+
+    let mut app = app(3);
+    app.insert_resource(config)
+        .insert_resource(GameMode::Level(level));
+    run_to_game_over(&mut app);
+
+    assert!(app.world.resource::<WaveDirector>().is_complete());
+    let enemies = app
+        .world
+        .query_filtered::<(), With<Enemy>>()
+        .iter(&app.world)
+        .count();
+    assert_eq!(enemies, 0);
+}